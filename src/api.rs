@@ -1,24 +1,286 @@
 use exonum::{
     api::{self, ServiceApiBuilder, ServiceApiState},
-    blockchain::{self, BlockProof, TransactionMessage},
+    blockchain::{self, BlockProof, TransactionMessage, TransactionSet},
     crypto::{Hash, PublicKey},
-    explorer::BlockchainExplorer,
     helpers::Height,
 };
-use exonum_merkledb::{ListProof, MapProof};
+use exonum_merkledb::{
+    BinaryValue, HashTag, IndexAccess, ListProof, MapProof, ObjectHash, Snapshot,
+};
+
+use super::{
+    schema::{Schema, ServiceTable},
+    SERVICE_ID,
+};
+use crate::{
+    checkpoint::{Checkpoint, ParticipantVersion},
+    config::ServiceConfig,
+    event::QueueEvent,
+    outcome::{OutcomeStatus, TransactionOutcome},
+    participant::Participant,
+    slot::Slot,
+    snapshot::SnapshotInfo,
+    stats::QueueStats,
+    transactions::{Error, ParticipantTransactions},
+};
 
-use super::{schema::Schema, SERVICE_ID};
-use crate::participant::Participant;
+/// Default number of tickets in the display feed lists.
+pub const DEFAULT_DISPLAY_LEN: u64 = 5;
+/// Maximum number of history records returned by the `info` endpoint.
+pub const MAX_HISTORY_LIMIT: u64 = 100;
+/// Default number of blocks in the operator throughput statistics.
+pub const DEFAULT_THROUGHPUT_BLOCKS: u64 = 10;
+/// Maximum number of blocks in the operator throughput statistics.
+pub const MAX_THROUGHPUT_BLOCKS: u64 = 1000;
+/// Maximum number of events returned by the `events` endpoint.
+pub const MAX_EVENTS_LIMIT: u64 = 1000;
 
 /// Get first participant key
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct GetFirstQuery {
+    /// Return the head of the queue at the end of the block at this height,
+    /// of the last block if omitted.
+    pub height: Option<u64>,
+}
+
+/// Participant at the head of the queue.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FirstParticipant {
+    /// Public key of the participant.
+    pub key: PublicKey,
+    /// Proof of the checkpoint recording the participant as the head.
+    pub checkpoint_proof: CheckpointProof,
+}
+
+/// Describes the query parameters for the `checkpoint` endpoint.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
-pub struct GetFirstQuery {}
+pub struct CheckpointQuery {
+    /// Height of the block.
+    pub height: u64,
+}
 
 /// Describes the query parameters for the `get_participant` endpoint.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+///
+/// Exactly one of the fields must be set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct ParticipantQuery {
     /// Public key of the queried participant.
+    pub pub_key: Option<PublicKey>,
+    /// Ticket number of the queried participant.
+    pub ticket: Option<u64>,
+    /// Attestation hash of the queried participant.
+    pub attestation: Option<Hash>,
+    /// External order id of the queried participant.
+    pub order_id: Option<String>,
+    /// Index of the first returned history record, zero by default.
+    pub history_from: Option<u64>,
+    /// Maximum number of returned history records, `MAX_HISTORY_LIMIT` by default.
+    pub history_limit: Option<u64>,
+    /// Don't return the history.
+    pub omit_history: Option<bool>,
+    /// Also return the participant state at the end of the block at this height.
+    pub height: Option<u64>,
+}
+
+impl ParticipantQuery {
+    /// Creates a query by the participant public key.
+    pub fn by_key(pub_key: PublicKey) -> Self {
+        Self {
+            pub_key: Some(pub_key),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the query for the given window of the history.
+    pub fn with_history(self, from: u64, limit: u64) -> Self {
+        Self {
+            history_from: Some(from),
+            history_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Returns the query without the history.
+    pub fn without_history(self) -> Self {
+        Self {
+            omit_history: Some(true),
+            ..self
+        }
+    }
+
+    /// Returns the query for the participant state at the given height.
+    pub fn at_height(self, height: u64) -> Self {
+        Self {
+            height: Some(height),
+            ..self
+        }
+    }
+}
+
+/// Describes the query parameters for the `table` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TableQuery {
+    /// Queried service table.
+    pub table: ServiceTable,
+}
+
+/// Describes the query parameters for the `errors` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ErrorsQuery {}
+
+/// Execution error emitted by the service transactions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ErrorInfo {
+    /// Stable error code.
+    pub code: u8,
+    /// Error identifier used in the error descriptions.
+    pub error: Error,
+    /// English error message.
+    pub message: String,
+}
+
+/// Proof of a service table against the block header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableProof {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Queried service table.
+    pub table: ServiceTable,
+    /// Current hash of the table.
+    pub table_hash: Hash,
+    /// Proof of the table in the service tables.
+    pub to_table: MapProof<Hash, Hash>,
+}
+
+/// Describes the query parameters for the `events` endpoint.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct EventsQuery {
+    /// Index of the first returned event, zero by default.
+    pub from: Option<u64>,
+    /// Maximum number of returned events, `MAX_EVENTS_LIMIT` by default.
+    pub limit: Option<u64>,
+}
+
+/// Range of the queue event log with the proof against the block header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventsRange {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Proof of the events table.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Total number of events in the log.
+    pub len: u64,
+    /// Index of the first returned event.
+    pub from: u64,
+    /// Proof of the returned events proving the length of the log, `None` if
+    /// the log is empty. If the range is past the end of the log, the proof
+    /// is for the last event.
+    pub proof: Option<ListProof<QueueEvent>>,
+    /// Returned events.
+    pub events: Vec<QueueEvent>,
+}
+
+/// Describes the query parameters for the `slots` endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SlotsQuery {
+    /// Store to list slots for, all stores if omitted.
+    pub store: Option<String>,
+}
+
+/// Occupancy of a pickup slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlotOccupancy {
+    /// `Hash` of the slot.
+    pub id: Hash,
+    /// The slot itself.
+    pub slot: Slot,
+    /// Number of free places.
+    pub available: u64,
+}
+
+/// Describes the query parameters for the `audit` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AuditQuery {
+    /// Public key of the audited participant.
+    pub pub_key: PublicKey,
+}
+
+/// Describes the query parameters for the full-scan `audit/all` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AuditAllQuery {}
+
+/// Comparison of the stored history summary with the history index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryAudit {
+    /// Public key of the audited participant.
     pub pub_key: PublicKey,
+    /// `history_len` stored in the participant.
+    pub stored_len: u64,
+    /// Actual length of the history index.
+    pub actual_len: u64,
+    /// `history_hash` stored in the participant.
+    pub stored_hash: Hash,
+    /// Hash of the history recomputed from its entries.
+    pub actual_hash: Hash,
+    /// Whether the stored values match the index.
+    pub consistent: bool,
+}
+
+impl HistoryAudit {
+    /// Compares the stored history summary of the participant with the
+    /// history entries, the hash is recomputed from the entries.
+    pub fn new(participant: &Participant, entries: &[Hash]) -> Self {
+        let actual_hash = list_hash(entries);
+        let actual_len = entries.len() as u64;
+
+        Self {
+            pub_key: participant.key,
+            stored_len: participant.history_len,
+            actual_len,
+            stored_hash: participant.history_hash,
+            actual_hash,
+            consistent: participant.history_len == actual_len
+                && participant.history_hash == actual_hash,
+        }
+    }
+}
+
+/// Computes the hash a `ProofListIndex` holding the entries would have.
+fn list_hash(entries: &[Hash]) -> Hash {
+    let mut level = entries
+        .iter()
+        .map(|entry| HashTag::hash_leaf(&entry.to_bytes()))
+        .collect::<Vec<_>>();
+    if level.is_empty() {
+        return HashTag::empty_list_hash();
+    }
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => HashTag::hash_node(left, right),
+                [single] => HashTag::hash_single_node(single),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    HashTag::hash_list_node(entries.len() as u64, level[0])
+}
+
+/// Result of auditing the histories of all participants.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditReport {
+    /// Number of checked participants.
+    pub checked: u64,
+    /// Participants with inconsistent histories.
+    pub mismatches: Vec<HistoryAudit>,
+}
+
+/// Describes the query parameters for the `ticket` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TicketQuery {
+    /// Ticket number of the queried participant.
+    pub ticket: u64,
 }
 
 /// Proof of existence for specific participant.
@@ -30,13 +292,111 @@ pub struct ParticipantProof {
     pub to_participant: MapProof<PublicKey, Participant>,
 }
 
-/// Participant history.
+/// Proof of the queue checkpoint of a past block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckpointProof {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Proof of the checkpoints table.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Proof of the checkpoint in this table.
+    pub to_checkpoint: MapProof<u64, Checkpoint>,
+    /// Proof of the block the checkpoint is recorded for.
+    pub checkpoint_block: BlockProof,
+}
+
+/// Proof of the participant version recorded at or before a past height.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VersionsProof {
+    /// Proof of the versions table.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Proof of the root hash of the participant versions in this table.
+    pub to_versions: MapProof<PublicKey, Hash>,
+    /// Number of the participant versions.
+    pub len: u64,
+    /// Proof of the last version at or before the height and the version
+    /// after it, or of the first version if it is recorded after the height.
+    /// `None` if the participant has no versions.
+    pub proof: Option<ListProof<ParticipantVersion>>,
+}
+
+/// Participant state at a past height.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParticipantAtHeight {
+    /// Participant at the end of the block, `None` if it was not added yet.
+    pub participant: Option<Participant>,
+    /// Proof of the checkpoint of the block.
+    pub checkpoint_proof: CheckpointProof,
+    /// Proof of the participant against the last block of the checkpoint proof.
+    pub versions_proof: VersionsProof,
+}
+
+/// Proof resolving a secondary key to the participant public key.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecondaryKeyProof {
+    /// Lookup by ticket number.
+    Ticket {
+        /// Proof of the tickets table.
+        to_table: MapProof<Hash, Hash>,
+        /// Proof of the ticket in this table.
+        to_key: MapProof<u64, PublicKey>,
+    },
+    /// Lookup by attestation hash.
+    Attestation {
+        /// Proof of the attestations table.
+        to_table: MapProof<Hash, Hash>,
+        /// Proof of the attestation in this table.
+        to_key: MapProof<Hash, PublicKey>,
+    },
+    /// Lookup by external order id.
+    OrderId {
+        /// Proof of the order ids table.
+        to_table: MapProof<Hash, Hash>,
+        /// Proof of the order id in this table.
+        to_key: MapProof<String, PublicKey>,
+    },
+}
+
+/// Window of the participant history.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ParticipantHistory {
-    /// Proof of the list of transaction hashes.
+    /// Index of the first record in the window.
+    pub from: u64,
+    /// Proof of the window of transaction and event hashes.
     pub proof: ListProof<Hash>,
-    /// List of above transactions.
-    pub transactions: Vec<TransactionMessage>,
+    /// Entries of the above hashes.
+    pub transactions: Vec<HistoryEntry>,
+}
+
+/// Entry of the participant history.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEntry {
+    /// Transaction referencing the participant.
+    Transaction(HistoryTransaction),
+    /// Event recorded when the block was committed, the participant
+    /// expiration.
+    Event(QueueEvent),
+}
+
+/// Transaction from the participant history.
+///
+/// Only `transaction` is checked against the signed `message` by
+/// `ParticipantInfo::verify`; `height`, `position` and `status` are
+/// reported by the node as is.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryTransaction {
+    /// Height of the block with the transaction, not verified.
+    pub height: u64,
+    /// Position of the transaction in the block, not verified.
+    pub position: u64,
+    /// Execution status, not verified.
+    pub status: OutcomeStatus,
+    /// Decoded transaction, checked against `message`.
+    pub transaction: ParticipantTransactions,
+    /// Signed transaction message.
+    pub message: TransactionMessage,
 }
 
 /// Participant information.
@@ -44,10 +404,183 @@ pub struct ParticipantHistory {
 pub struct ParticipantInfo {
     /// Proof of the last block.
     pub block_proof: BlockProof,
+    /// Proof of the secondary key, if the participant was queried by one.
+    pub secondary_proof: Option<SecondaryKeyProof>,
     /// Proof of the appropriate participant.
     pub participant_proof: ParticipantProof,
-    /// History of the appropriate participant.
+    /// Requested window of the participant history, `None` if the participant
+    /// doesn't exist or the history is omitted.
     pub participant_history: Option<ParticipantHistory>,
+    /// Participant state at the queried height, if one was set.
+    pub at_height: Option<ParticipantAtHeight>,
+}
+
+/// Describes the query parameters for the public and private `stats` endpoints.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct StatsQuery {}
+
+/// Describes the query parameters for the private `stats` endpoint.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct OperatorStatsQuery {
+    /// Number of the last blocks to report the throughput for.
+    pub blocks: Option<u64>,
+}
+
+/// Number of purchases in a block.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockThroughput {
+    /// Height of the block.
+    pub height: u64,
+    /// Number of purchases in the block.
+    pub bought: u64,
+}
+
+/// Queue statistics for operators.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperatorStats {
+    /// Number of participants waiting in the queue.
+    pub waiting: u64,
+    /// Number of participants who bought a phone.
+    pub bought: u64,
+    /// Number of removed participants.
+    pub removed: u64,
+    /// Number of committed blocks.
+    pub blocks: u64,
+    /// Average number of purchases per block over the reported blocks.
+    pub throughput: f64,
+    /// Number of purchases in each of the last blocks, oldest first.
+    pub per_block: Vec<BlockThroughput>,
+}
+
+/// Describes the query parameters for the `search` endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchQuery {
+    /// Hex prefix of the participant key.
+    pub prefix: String,
+}
+
+/// Describes the query parameters for the `dump` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DumpQuery {}
+
+/// Participant together with its database record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParticipantRecord {
+    /// Decoded participant.
+    pub participant: Participant,
+    /// Hex-encoded protobuf record as stored in the database.
+    pub raw: String,
+}
+
+/// Outcome of a transaction referencing the participant.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionOutcomeInfo {
+    /// `Hash` of the transaction.
+    pub tx_hash: Hash,
+    /// Height of the block with the transaction.
+    pub height: u64,
+    /// Position of the transaction in the block.
+    pub position: u64,
+    /// Execution status.
+    pub status: OutcomeStatus,
+}
+
+impl From<TransactionOutcome> for TransactionOutcomeInfo {
+    fn from(outcome: TransactionOutcome) -> Self {
+        Self {
+            status: outcome.status(),
+            tx_hash: outcome.tx_hash,
+            height: outcome.height,
+            position: outcome.position,
+        }
+    }
+}
+
+/// Queue statistics with the proof against the block header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueStatsInfo {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Proof of the statistics entry in the service tables.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Aggregated queue statistics.
+    pub stats: QueueStats,
+}
+
+/// Describes the query parameters for the `snapshot` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SnapshotQuery {}
+
+/// Genesis snapshot information with the proof against the block header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotProof {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Proof of the snapshot entry in the service tables.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Snapshot imported at genesis, `None` if the queue started empty.
+    pub snapshot: Option<SnapshotInfo>,
+}
+
+/// Describes the query parameters for the `config` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ConfigQuery {}
+
+/// Service configuration with the proof against the block header.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigProof {
+    /// Proof of the last block.
+    pub block_proof: BlockProof,
+    /// Proof of the configuration entry in the service tables.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Service configuration applied at genesis.
+    pub config: ServiceConfig,
+}
+
+/// Describes the query parameters for the `display` endpoint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct DisplayQuery {
+    /// Number of waiting participants to show after the head.
+    pub next: Option<u64>,
+    /// Number of recently served participants to show.
+    pub served: Option<u64>,
+    /// Show the queue at the end of the block at this height, of the last
+    /// block if omitted.
+    pub height: Option<u64>,
+}
+
+/// Ticket shown on the store screens.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DisplayTicket {
+    /// Ticket label, e.g. `A-042`.
+    pub ticket: String,
+    /// Public key of the participant.
+    pub key: PublicKey,
+}
+
+impl From<&Participant> for DisplayTicket {
+    fn from(participant: &Participant) -> Self {
+        DisplayTicket {
+            ticket: participant.ticket_label(),
+            key: participant.key,
+        }
+    }
+}
+
+/// "Now serving" feed for the store screens.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueDisplay {
+    /// Participant at the head of the queue.
+    pub now_serving: Option<DisplayTicket>,
+    /// Next waiting participants.
+    pub next: Vec<DisplayTicket>,
+    /// Recently served participants, the latest first.
+    pub recently_served: Vec<DisplayTicket>,
+    /// Proof of the checkpoint of the shown block.
+    pub checkpoint_proof: CheckpointProof,
+    /// Proofs of the participant versions of the tickets in the order
+    /// `now_serving`, `next`, `recently_served`, see `QueueDisplay::verify`.
+    pub versions_proofs: Vec<VersionsProof>,
 }
 
 /// Public service API description.
@@ -61,8 +594,105 @@ impl PublicApi {
         query: ParticipantQuery,
     ) -> api::Result<ParticipantInfo> {
         let snapshot = state.snapshot();
-        let general_schema = blockchain::Schema::new(&snapshot);
-        let currency_schema = Schema::new(&snapshot);
+        let (pub_key, secondary_proof) = Self::resolve_key(&snapshot, &query)?;
+        let mut info = Self::participant_info_by_key(&snapshot, &pub_key, &query)?;
+        info.secondary_proof = secondary_proof;
+
+        if let Some(height) = query.height {
+            let schema = Schema::new(&snapshot);
+            info.at_height = Some(ParticipantAtHeight {
+                checkpoint_proof: Self::checkpoint_proof(&snapshot, height)?,
+                participant: schema.participant_at(&pub_key, height),
+                versions_proof: Self::versions_proof(&snapshot, &pub_key, height),
+            });
+        }
+        Ok(info)
+    }
+
+    /// Endpoint for getting a single participant by the ticket number.
+    fn ticket_info(state: &ServiceApiState, query: TicketQuery) -> api::Result<ParticipantInfo> {
+        let query = ParticipantQuery {
+            ticket: Some(query.ticket),
+            ..ParticipantQuery::default()
+        };
+        Self::participant_info(state, query)
+    }
+
+    /// Resolves the participant key from the query together with the proof
+    /// of the secondary key, if one was used.
+    fn resolve_key(
+        snapshot: &dyn Snapshot,
+        query: &ParticipantQuery,
+    ) -> api::Result<(PublicKey, Option<SecondaryKeyProof>)> {
+        let general_schema = blockchain::Schema::new(snapshot);
+        let schema = Schema::new(snapshot);
+
+        let not_found = || api::Error::NotFound("Participant not found".to_owned());
+
+        match query {
+            ParticipantQuery {
+                pub_key: Some(pub_key),
+                ticket: None,
+                attestation: None,
+                order_id: None,
+                ..
+            } => Ok((*pub_key, None)),
+            ParticipantQuery {
+                pub_key: None,
+                ticket: Some(ticket),
+                attestation: None,
+                order_id: None,
+                ..
+            } => {
+                let pub_key = schema.ticket_key(*ticket).ok_or_else(not_found)?;
+                let proof = SecondaryKeyProof::Ticket {
+                    to_table: Self::table_proof(&general_schema, ServiceTable::Tickets),
+                    to_key: schema.tickets().get_proof(*ticket),
+                };
+                Ok((pub_key, Some(proof)))
+            }
+            ParticipantQuery {
+                pub_key: None,
+                ticket: None,
+                attestation: Some(attestation),
+                order_id: None,
+                ..
+            } => {
+                let pub_key = schema.attestations().get(attestation).ok_or_else(not_found)?;
+                let proof = SecondaryKeyProof::Attestation {
+                    to_table: Self::table_proof(&general_schema, ServiceTable::Attestations),
+                    to_key: schema.attestations().get_proof(*attestation),
+                };
+                Ok((pub_key, Some(proof)))
+            }
+            ParticipantQuery {
+                pub_key: None,
+                ticket: None,
+                attestation: None,
+                order_id: Some(order_id),
+                ..
+            } => {
+                let pub_key = schema.order_ids().get(order_id).ok_or_else(not_found)?;
+                let proof = SecondaryKeyProof::OrderId {
+                    to_table: Self::table_proof(&general_schema, ServiceTable::OrderIds),
+                    to_key: schema.order_ids().get_proof(order_id.clone()),
+                };
+                Ok((pub_key, Some(proof)))
+            }
+            _ => Err(api::Error::BadRequest(
+                "Exactly one of `pub_key`, `ticket`, `attestation` or `order_id` must be set"
+                    .to_owned(),
+            )),
+        }
+    }
+
+    fn participant_info_by_key(
+        snapshot: &dyn Snapshot,
+        pub_key: &PublicKey,
+        query: &ParticipantQuery,
+    ) -> api::Result<ParticipantInfo> {
+        let general_schema = blockchain::Schema::new(snapshot);
+        let currency_schema = Schema::new(snapshot);
 
         let max_height = general_schema.block_hashes_by_height().len() - 1;
 
@@ -71,48 +701,425 @@ impl PublicApi {
             .unwrap();
 
         let to_table: MapProof<Hash, Hash> =
-            general_schema.get_proof_to_service_table(SERVICE_ID, 0);
+            Self::table_proof(&general_schema, ServiceTable::Participants);
 
         let to_participant: MapProof<PublicKey, Participant> =
-            currency_schema.participants().get_proof(query.pub_key);
+            currency_schema.participants().get_proof(*pub_key);
 
         let participant_proof = ParticipantProof {
             to_table,
             to_participant,
         };
 
-        let participant = currency_schema.participant(&query.pub_key);
+        let participant = currency_schema.participant(pub_key);
+        let history = currency_schema.participant_history(pub_key);
 
-        let explorer = BlockchainExplorer::new(state.blockchain());
+        let participant_history = match participant {
+            // Participants imported from the genesis snapshot may have no history.
+            Some(_) if !query.omit_history.unwrap_or(false) && !history.is_empty() => {
+                let from = query.history_from.unwrap_or(0);
+                let limit = query.history_limit.unwrap_or(MAX_HISTORY_LIMIT);
+                if from >= history.len() || limit == 0 || limit > MAX_HISTORY_LIMIT {
+                    return Err(api::Error::BadRequest(format!(
+                        "Invalid history window, history length is {}, max limit is {}",
+                        history.len(),
+                        MAX_HISTORY_LIMIT
+                    )));
+                }
+                let to = history.len().min(from + limit);
+                let proof = history.get_range_proof(from..to);
 
-        let participant_history = participant.map(|_| {
-            let history = currency_schema.participant_history(&query.pub_key);
-            let proof = history.get_range_proof(0..history.len());
+                let transactions = (from..to)
+                    .map(|index| {
+                        Self::history_entry(
+                            &general_schema,
+                            &currency_schema,
+                            &history.get(index).unwrap(),
+                        )
+                    })
+                    .collect::<api::Result<Vec<_>>>()?;
 
-            let transactions = history
-                .iter()
-                .map(|record| explorer.transaction_without_proof(&record).unwrap())
-                .collect::<Vec<_>>();
-
-            ParticipantHistory {
-                proof,
-                transactions,
+                Some(ParticipantHistory {
+                    from,
+                    proof,
+                    transactions,
+                })
             }
-        });
+            _ => None,
+        };
 
         Ok(ParticipantInfo {
             block_proof,
+            secondary_proof: None,
             participant_proof,
             participant_history,
+            at_height: None,
+        })
+    }
+
+    /// Returns the history entry for the transaction or the event hash.
+    fn history_entry<T>(
+        general_schema: &blockchain::Schema<T>,
+        schema: &Schema<T>,
+        tx_hash: &Hash,
+    ) -> api::Result<HistoryEntry>
+    where
+        T: IndexAccess,
+    {
+        if let Some(event) = schema.history_events().get(tx_hash) {
+            return Ok(HistoryEntry::Event(event));
+        }
+
+        let not_found = |what| {
+            api::Error::InternalError(format_err!(
+                "{} of the transaction {} from the history is not found",
+                what,
+                tx_hash.to_hex()
+            ))
+        };
+
+        let message = general_schema
+            .transactions()
+            .get(tx_hash)
+            .ok_or_else(|| not_found("Message"))?;
+        let location = general_schema
+            .transactions_locations()
+            .get(tx_hash)
+            .ok_or_else(|| not_found("Location"))?;
+        let result = general_schema
+            .transaction_results()
+            .get(tx_hash)
+            .ok_or_else(|| not_found("Result"))?;
+        let transaction = ParticipantTransactions::tx_from_raw(message.payload().clone())
+            .map_err(api::Error::InternalError)?;
+
+        Ok(HistoryEntry::Transaction(HistoryTransaction {
+            height: location.block_height().0,
+            position: location.position_in_block(),
+            status: OutcomeStatus::from_result(&result.0),
+            transaction,
+            message,
+        }))
+    }
+
+    /// Returns the height of the last committed block.
+    fn last_height(snapshot: &dyn Snapshot) -> u64 {
+        blockchain::Schema::new(snapshot).block_hashes_by_height().len() - 1
+    }
+
+    /// Returns the checkpoint of the block at the given height.
+    fn checkpoint_at(snapshot: &dyn Snapshot, height: u64) -> api::Result<Checkpoint> {
+        let general_schema = blockchain::Schema::new(snapshot);
+        let schema = Schema::new(snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+        if height > max_height {
+            return Err(api::Error::BadRequest(format!(
+                "Height {} is above the last block height {}",
+                height, max_height
+            )));
+        }
+        schema
+            .checkpoint(height)
+            .ok_or_else(|| api::Error::NotFound(format!("No checkpoint for height {}", height)))
+    }
+
+    /// Returns the proof of the checkpoint of the block at the given height.
+    fn checkpoint_proof(snapshot: &dyn Snapshot, height: u64) -> api::Result<CheckpointProof> {
+        Self::checkpoint_at(snapshot, height)?;
+
+        let general_schema = blockchain::Schema::new(snapshot);
+        let schema = Schema::new(snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+
+        Ok(CheckpointProof {
+            block_proof: general_schema
+                .block_and_precommits(Height(max_height))
+                .unwrap(),
+            to_table: Self::table_proof(&general_schema, ServiceTable::Checkpoints),
+            to_checkpoint: schema.checkpoints().get_proof(height),
+            checkpoint_block: general_schema
+                .block_and_precommits(Height(height))
+                .unwrap(),
+        })
+    }
+
+    /// Returns the proof of the participant version at the given height.
+    fn versions_proof(snapshot: &dyn Snapshot, pub_key: &PublicKey, height: u64) -> VersionsProof {
+        let general_schema = blockchain::Schema::new(snapshot);
+        let schema = Schema::new(snapshot);
+
+        let versions = schema.participant_versions(pub_key);
+        let len = versions.len();
+        let until = schema.versions_until(pub_key, height);
+        let proof = if len > 0 {
+            Some(versions.get_range_proof(until.saturating_sub(1)..len.min(until + 1)))
+        } else {
+            None
+        };
+
+        VersionsProof {
+            to_table: Self::table_proof(&general_schema, ServiceTable::Versions),
+            to_versions: schema.versions().get_proof(*pub_key),
+            len,
+            proof,
+        }
+    }
+
+    /// Endpoint for streaming the queue event log.
+    fn events(state: &ServiceApiState, query: EventsQuery) -> api::Result<EventsRange> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let from = query.from.unwrap_or(0);
+        let limit = query.limit.unwrap_or(MAX_EVENTS_LIMIT);
+        if limit == 0 || limit > MAX_EVENTS_LIMIT {
+            return Err(api::Error::BadRequest(format!(
+                "Invalid events limit, max limit is {}",
+                MAX_EVENTS_LIMIT
+            )));
+        }
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        let events = schema.events();
+        let len = events.len();
+        let to = len.min(from.saturating_add(limit));
+        let (proof, events) = if from < to {
+            (
+                Some(events.get_range_proof(from..to)),
+                (from..to).filter_map(|i| events.get(i)).collect(),
+            )
+        } else if len > 0 {
+            (Some(events.get_range_proof(len - 1..len)), Vec::new())
+        } else {
+            (None, Vec::new())
+        };
+
+        Ok(EventsRange {
+            block_proof,
+            to_table: Self::table_proof(&general_schema, ServiceTable::Events),
+            len,
+            from,
+            proof,
+            events,
+        })
+    }
+
+    /// Endpoint for getting the proof of the checkpoint of a past block.
+    fn checkpoint(
+        state: &ServiceApiState,
+        query: CheckpointQuery,
+    ) -> api::Result<CheckpointProof> {
+        Self::checkpoint_proof(&state.snapshot(), query.height)
+    }
+
+    /// Returns the proof of the registered table against the service tables.
+    fn table_proof<T>(
+        general_schema: &blockchain::Schema<T>,
+        table: ServiceTable,
+    ) -> MapProof<Hash, Hash>
+    where
+        T: IndexAccess,
+    {
+        general_schema.get_proof_to_service_table(SERVICE_ID, table.index())
+    }
+
+    /// Endpoint for listing execution error codes.
+    fn errors(_: &ServiceApiState, _: ErrorsQuery) -> api::Result<Vec<ErrorInfo>> {
+        Ok(Error::ALL
+            .iter()
+            .map(|&error| ErrorInfo {
+                code: error.code(),
+                error,
+                message: error.to_string(),
+            })
+            .collect())
+    }
+
+    /// Endpoint for getting the proof of a registered table.
+    fn table(state: &ServiceApiState, query: TableQuery) -> api::Result<TableProof> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        Ok(TableProof {
+            block_proof,
+            table: query.table,
+            table_hash: schema.table_hash(query.table),
+            to_table: Self::table_proof(&general_schema, query.table),
+        })
+    }
+
+    /// Endpoint for getting aggregated queue statistics.
+    fn queue_stats(state: &ServiceApiState, _: StatsQuery) -> api::Result<QueueStatsInfo> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        let to_table: MapProof<Hash, Hash> =
+            Self::table_proof(&general_schema, ServiceTable::QueueStats);
+
+        Ok(QueueStatsInfo {
+            block_proof,
+            to_table,
+            stats: schema.queue_stats(),
+        })
+    }
+
+    /// Endpoint for getting the snapshot the queue was imported from.
+    fn snapshot(state: &ServiceApiState, _: SnapshotQuery) -> api::Result<SnapshotProof> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        Ok(SnapshotProof {
+            block_proof,
+            to_table: Self::table_proof(&general_schema, ServiceTable::Snapshot),
+            snapshot: schema.snapshot(),
+        })
+    }
+
+    /// Endpoint for getting the service configuration.
+    fn config(state: &ServiceApiState, _: ConfigQuery) -> api::Result<ConfigProof> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        Ok(ConfigProof {
+            block_proof,
+            to_table: Self::table_proof(&general_schema, ServiceTable::Config),
+            config: schema.config(),
+        })
+    }
+
+    /// Endpoint for the "now serving" display feed.
+    fn display(state: &ServiceApiState, query: DisplayQuery) -> api::Result<QueueDisplay> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+
+        let next = query.next.unwrap_or(DEFAULT_DISPLAY_LEN) as usize;
+        let served = query.served.unwrap_or(DEFAULT_DISPLAY_LEN);
+
+        let height = query.height.unwrap_or_else(|| Self::last_height(&snapshot));
+        let checkpoint_proof = Self::checkpoint_proof(&snapshot, height)?;
+        let served_len = Self::checkpoint_at(&snapshot, height)?.stats.bought;
+        let waiting = schema.waiting_participants_at(height);
+
+        let now_serving = waiting.first().map(DisplayTicket::from);
+        let next = waiting
+            .iter()
+            .skip(1)
+            .take(next)
+            .map(DisplayTicket::from)
+            .collect::<Vec<_>>();
+
+        let served_list = schema.served();
+        let recently_served = (served_len.saturating_sub(served)..served_len)
+            .rev()
+            .filter_map(|i| served_list.get(i))
+            .filter_map(|key| schema.participant_at(&key, height))
+            .map(|participant| DisplayTicket::from(&participant))
+            .collect::<Vec<_>>();
+
+        let versions_proofs = now_serving
+            .iter()
+            .chain(&next)
+            .chain(&recently_served)
+            .map(|ticket| Self::versions_proof(&snapshot, &ticket.key, height))
+            .collect();
+
+        Ok(QueueDisplay {
+            now_serving,
+            next,
+            recently_served,
+            checkpoint_proof,
+            versions_proofs,
         })
     }
 
-    fn get_first(state: &ServiceApiState, _: GetFirstQuery) -> api::Result<String> {
+    /// Endpoint for getting the participant at the head of the queue with
+    /// the proof of the checkpoint recording it.
+    fn get_first(state: &ServiceApiState, query: GetFirstQuery) -> api::Result<FirstParticipant> {
+        let snapshot = state.snapshot();
+        let height = query.height.unwrap_or_else(|| Self::last_height(&snapshot));
+
+        let key = Self::checkpoint_at(&snapshot, height)?
+            .head()
+            .ok_or_else(|| api::Error::NotFound("Queue is empty".to_owned()))?;
+        Ok(FirstParticipant {
+            key,
+            checkpoint_proof: Self::checkpoint_proof(&snapshot, height)?,
+        })
+    }
+
+    fn slots(state: &ServiceApiState, query: SlotsQuery) -> api::Result<Vec<SlotOccupancy>> {
         let snapshot = state.snapshot();
         let schema = Schema::new(&snapshot);
-        let first = schema.first_participant().unwrap();
 
-        Ok(first.key.to_hex())
+        let slots = schema
+            .slots()
+            .iter()
+            .filter(|(_, slot)| query.store.as_ref().map_or(true, |store| *store == slot.store))
+            .map(|(id, slot)| SlotOccupancy {
+                id,
+                available: slot.capacity.saturating_sub(slot.booked),
+                slot,
+            })
+            .collect();
+
+        Ok(slots)
+    }
+
+    fn audit_participant<T>(schema: &Schema<T>, participant: &Participant) -> HistoryAudit
+    where
+        T: IndexAccess,
+    {
+        let entries = schema
+            .participant_history(&participant.key)
+            .iter()
+            .collect::<Vec<_>>();
+        HistoryAudit::new(participant, &entries)
+    }
+
+    /// Endpoint for auditing the history of a single participant.
+    fn audit(state: &ServiceApiState, query: AuditQuery) -> api::Result<HistoryAudit> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let participant = schema
+            .participant(&query.pub_key)
+            .ok_or_else(|| api::Error::NotFound("Participant not found".to_owned()))?;
+
+        Ok(Self::audit_participant(&schema, &participant))
     }
 
     /// Wires the above endpoint to public scope of the given `ServiceApiBuilder`.
@@ -120,6 +1127,164 @@ impl PublicApi {
         builder
             .public_scope()
             .endpoint("v1/iphone_queue/info", Self::participant_info)
-            .endpoint("v1/iphone_queue/get_first", Self::get_first);
+            .endpoint("v1/iphone_queue/ticket", Self::ticket_info)
+            .endpoint("v1/iphone_queue/get_first", Self::get_first)
+            .endpoint("v1/iphone_queue/slots", Self::slots)
+            .endpoint("v1/iphone_queue/audit", Self::audit)
+            .endpoint("v1/iphone_queue/stats", Self::queue_stats)
+            .endpoint("v1/iphone_queue/display", Self::display)
+            .endpoint("v1/iphone_queue/table", Self::table)
+            .endpoint("v1/iphone_queue/errors", Self::errors)
+            .endpoint("v1/iphone_queue/checkpoint", Self::checkpoint)
+            .endpoint("v1/iphone_queue/events", Self::events)
+            .endpoint("v1/iphone_queue/snapshot", Self::snapshot)
+            .endpoint("v1/iphone_queue/config", Self::config);
+    }
+}
+
+/// Private service API description.
+#[derive(Debug, Clone, Copy)]
+pub struct PrivateApi;
+
+impl PrivateApi {
+    /// Endpoint for getting queue statistics.
+    fn stats(
+        state: &ServiceApiState,
+        query: OperatorStatsQuery,
+    ) -> api::Result<OperatorStats> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = Schema::new(&snapshot);
+
+        let window = query.blocks.unwrap_or(DEFAULT_THROUGHPUT_BLOCKS);
+        if window == 0 || window > MAX_THROUGHPUT_BLOCKS {
+            return Err(api::Error::BadRequest(format!(
+                "Invalid number of blocks, max is {}",
+                MAX_THROUGHPUT_BLOCKS
+            )));
+        }
+
+        let QueueStats {
+            waiting,
+            bought,
+            removed,
+            ..
+        } = schema.queue_stats();
+
+        // Purchases in a block are the difference of the counters in the
+        // checkpoints of the block and the previous one.
+        let blocks = general_schema.block_hashes_by_height().len();
+        let per_block = (blocks.saturating_sub(window).max(1)..blocks)
+            .filter_map(|height| {
+                let checkpoint = schema.checkpoint(height)?;
+                let previous = schema
+                    .checkpoint(height - 1)
+                    .map_or(0, |checkpoint| checkpoint.stats.bought);
+                Some(BlockThroughput {
+                    height,
+                    bought: checkpoint.stats.bought - previous,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let throughput = if per_block.is_empty() {
+            0.0
+        } else {
+            let total = per_block.iter().map(|block| block.bought).sum::<u64>();
+            total as f64 / per_block.len() as f64
+        };
+
+        Ok(OperatorStats {
+            waiting,
+            bought,
+            removed,
+            blocks,
+            throughput,
+            per_block,
+        })
+    }
+
+    /// Endpoint for searching participants by key prefix.
+    fn search(state: &ServiceApiState, query: SearchQuery) -> api::Result<Vec<Participant>> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+        let prefix = query.prefix.to_lowercase();
+
+        let participants = schema
+            .participants()
+            .iter()
+            .filter(|(key, _)| key.to_hex().starts_with(&prefix))
+            .map(|(_, participant)| participant)
+            .collect();
+
+        Ok(participants)
+    }
+
+    /// Endpoint for getting outcomes of all transactions referencing the
+    /// participant, failed ones included.
+    fn transactions(
+        state: &ServiceApiState,
+        query: ParticipantQuery,
+    ) -> api::Result<Vec<TransactionOutcomeInfo>> {
+        let snapshot = state.snapshot();
+        let (pub_key, _) = PublicApi::resolve_key(&snapshot, &query)?;
+        let schema = Schema::new(&snapshot);
+
+        let outcomes = schema
+            .participant_transactions(&pub_key)
+            .iter()
+            .map(TransactionOutcomeInfo::from)
+            .collect();
+
+        Ok(outcomes)
+    }
+
+    /// Endpoint for dumping all participants with raw records.
+    fn dump(state: &ServiceApiState, _: DumpQuery) -> api::Result<Vec<ParticipantRecord>> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+
+        let records = schema
+            .participants()
+            .iter()
+            .map(|(_, participant)| ParticipantRecord {
+                raw: hex::encode(participant.to_bytes()),
+                participant,
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// Endpoint for auditing the histories of all participants.
+    fn audit_all(state: &ServiceApiState, _: AuditAllQuery) -> api::Result<AuditReport> {
+        let snapshot = state.snapshot();
+        let schema = Schema::new(&snapshot);
+
+        let mut checked = 0;
+        let mut mismatches = Vec::new();
+        for (_, participant) in schema.participants().iter() {
+            checked += 1;
+            let audit = PublicApi::audit_participant(&schema, &participant);
+            if !audit.consistent {
+                mismatches.push(audit);
+            }
+        }
+
+        Ok(AuditReport {
+            checked,
+            mismatches,
+        })
+    }
+
+    /// Wires the above endpoints to private scope of the given `ServiceApiBuilder`.
+    pub fn wire(builder: &mut ServiceApiBuilder) {
+        builder
+            .private_scope()
+            .endpoint("v1/iphone_queue/stats", Self::stats)
+            .endpoint("v1/iphone_queue/search", Self::search)
+            .endpoint("v1/iphone_queue/dump", Self::dump)
+            .endpoint("v1/iphone_queue/transactions", Self::transactions)
+            .endpoint("v1/iphone_queue/audit/all", Self::audit_all);
     }
 }
@@ -0,0 +1,241 @@
+//! Command-line tool for the queue operators.
+//!
+//! Signs queue transactions with keys stored in local files, posts them to a
+//! node and prints human-readable results.
+extern crate clap;
+extern crate exonum;
+extern crate failure;
+extern crate hex;
+extern crate iphone_queue;
+
+use clap::ArgMatches;
+use exonum::crypto::{self, Hash, PublicKey};
+use failure::{bail, Error};
+use hex::FromHex;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use iphone_queue::{
+    cli::{self, KeyFile},
+    client::QueueClient,
+};
+
+/// Time to wait for a transaction to be committed.
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Creates the client verifying the responses against the validator keys
+/// given on the command line, the keys returned by the node can't be used
+/// to check its own responses.
+fn client(matches: &ArgMatches) -> Result<QueueClient, Error> {
+    let node = matches.value_of("node").unwrap();
+    Ok(QueueClient::new(node, cli::validator_keys(matches)?))
+}
+
+fn participant_key(matches: &ArgMatches, keys: &KeyFile) -> Result<PublicKey, Error> {
+    match matches.value_of("participant") {
+        Some(key) => Ok(PublicKey::from_hex(key)?),
+        None => Ok(keys.public_key),
+    }
+}
+
+fn wait(client: &QueueClient, tx_hash: &Hash) -> Result<(), Error> {
+    println!(
+        "Transaction {} sent, waiting for commit...",
+        tx_hash.to_hex()
+    );
+    client.wait_for_commit(tx_hash, COMMIT_TIMEOUT)?;
+    println!("Transaction {} committed", tx_hash.to_hex());
+    Ok(())
+}
+
+fn keygen(matches: &ArgMatches) -> Result<(), Error> {
+    let output = matches.value_of("output").unwrap();
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let keys = KeyFile {
+        public_key,
+        secret_key,
+    };
+    keys.save(output)?;
+    println!("Public key: {}", public_key.to_hex());
+    println!("Keys written to {}", output);
+    Ok(())
+}
+
+fn add(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+    let key = participant_key(matches, &keys)?;
+    let timestamp = match matches.value_of("timestamp") {
+        Some(timestamp) => timestamp.parse()?,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    let deposit = cli::number(matches, "deposit")?;
+
+    let client = client(matches)?;
+    let tx_hash = client.add(
+        &key,
+        timestamp,
+        deposit,
+        (&keys.public_key, &keys.secret_key),
+    )?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &key)
+}
+
+fn publish_slot(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+    let store = matches.value_of("store").unwrap();
+    let start = cli::number(matches, "start")?;
+    let end = cli::number(matches, "end")?;
+    let capacity = cli::number(matches, "capacity")?;
+
+    let client = QueueClient::new(matches.value_of("node").unwrap(), Vec::new());
+    let slot = client.publish_slot(
+        store,
+        start,
+        end,
+        capacity,
+        (&keys.public_key, &keys.secret_key),
+    )?;
+    wait(&client, &slot)?;
+    println!("Slot: {}", slot.to_hex());
+    Ok(())
+}
+
+fn book_slot(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+    let key = participant_key(matches, &keys)?;
+    let slot = cli::slot_id(matches)?;
+
+    let client = client(matches)?;
+    let tx_hash = client.book_slot(&key, &slot, (&keys.public_key, &keys.secret_key))?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &key)
+}
+
+fn buy(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+    let key = participant_key(matches, &keys)?;
+    let store = matches.value_of("store").unwrap();
+
+    let client = client(matches)?;
+    let tx_hash = client.buy(&key, store, (&keys.public_key, &keys.secret_key))?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &key)
+}
+
+fn remove(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+    let key = participant_key(matches, &keys)?;
+
+    let client = client(matches)?;
+    let tx_hash = client.remove(&key, (&keys.public_key, &keys.secret_key))?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &key)
+}
+
+fn pause(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+
+    let client = client(matches)?;
+    let tx_hash = client.pause((&keys.public_key, &keys.secret_key))?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &keys.public_key)
+}
+
+fn resume(matches: &ArgMatches) -> Result<(), Error> {
+    let keys = KeyFile::load(matches.value_of("keys").unwrap())?;
+
+    let client = client(matches)?;
+    let tx_hash = client.resume((&keys.public_key, &keys.secret_key))?;
+    wait(&client, &tx_hash)?;
+    print_participant(&client, &keys.public_key)
+}
+
+fn status(matches: &ArgMatches) -> Result<(), Error> {
+    let tx_hash = Hash::from_hex(matches.value_of("hash").unwrap())?;
+    let client = QueueClient::new(matches.value_of("node").unwrap(), Vec::new());
+    println!("{:?}", client.tx_status(&tx_hash)?);
+    Ok(())
+}
+
+fn first(matches: &ArgMatches) -> Result<(), Error> {
+    let client = client(matches)?;
+    println!("{}", client.first()?.to_hex());
+    Ok(())
+}
+
+fn list(matches: &ArgMatches) -> Result<(), Error> {
+    let next = matches.value_of("next").unwrap().parse()?;
+    let client = client(matches)?;
+    let display = client.display(next)?;
+
+    println!("Queue (proofs verified)");
+    match display.now_serving {
+        Some(ticket) => println!("Now serving: {} ({})", ticket.ticket, ticket.key.to_hex()),
+        None => println!("Queue is empty"),
+    }
+    for ticket in display.next {
+        println!("Next:        {} ({})", ticket.ticket, ticket.key.to_hex());
+    }
+    for ticket in display.recently_served {
+        println!("Served:      {} ({})", ticket.ticket, ticket.key.to_hex());
+    }
+    Ok(())
+}
+
+fn verify(matches: &ArgMatches) -> Result<(), Error> {
+    let key = PublicKey::from_hex(matches.value_of("participant").unwrap())?;
+    let client = client(matches)?;
+    print_participant(&client, &key)
+}
+
+/// Fetches the participant, verifies the proofs and prints it.
+fn print_participant(client: &QueueClient, key: &PublicKey) -> Result<(), Error> {
+    let participant = match client.participant(key)? {
+        Some(participant) => participant,
+        None => bail!("Participant {} not found", key.to_hex()),
+    };
+
+    let status = if participant.have_bought {
+        "bought"
+    } else if participant.removed {
+        "removed"
+    } else if participant.paused {
+        "paused"
+    } else {
+        "waiting"
+    };
+    println!("Participant {} (proofs verified)", key.to_hex());
+    println!("  ticket:    {}", participant.ticket_label());
+    println!("  timestamp: {}", participant.timestamp);
+    println!("  status:    {}", status);
+    println!("  history:   {} records", participant.history_len);
+    Ok(())
+}
+
+fn main() {
+    exonum::crypto::init();
+
+    let matches = cli::app().get_matches();
+
+    let result = match matches.subcommand() {
+        ("keygen", Some(matches)) => keygen(matches),
+        ("add", Some(matches)) => add(matches),
+        ("publish-slot", Some(matches)) => publish_slot(matches),
+        ("book-slot", Some(matches)) => book_slot(matches),
+        ("buy", Some(matches)) => buy(matches),
+        ("remove", Some(matches)) => remove(matches),
+        ("pause", Some(matches)) => pause(matches),
+        ("resume", Some(matches)) => resume(matches),
+        ("status", Some(matches)) => status(matches),
+        ("first", Some(matches)) => first(matches),
+        ("list", Some(matches)) => list(matches),
+        ("verify", Some(matches)) => verify(matches),
+        _ => unreachable!(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
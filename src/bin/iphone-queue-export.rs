@@ -0,0 +1,96 @@
+//! Exports the queue state from the node database for offline reports.
+//!
+//! The database is opened read-only and is never created: the export fails
+//! if the directory doesn't exist. It doesn't take the database lock, so a
+//! running node can be exported at the last block it had committed.
+//!
+//! With `--sign <keys>` the participants are written as a signed snapshot
+//! to seed the queue of a new network with `--queue-snapshot`.
+extern crate clap;
+extern crate exonum;
+extern crate failure;
+extern crate iphone_queue;
+extern crate serde_json;
+
+use clap::{App, Arg, ArgMatches};
+use exonum::blockchain;
+use failure::Error;
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use iphone_queue::{
+    cli::KeyFile,
+    export::{self, ExportFormat},
+    snapshot::{QueueSnapshot, SnapshotParticipant},
+};
+
+fn run(matches: &ArgMatches) -> Result<(), Error> {
+    let format: ExportFormat = matches.value_of("format").unwrap().parse()?;
+    let snapshot = export::open_read_only(matches.value_of("db").unwrap())?;
+
+    let records = export::export_records(&snapshot);
+    let writer: Box<dyn Write> = match matches.value_of("output") {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    match matches.value_of("sign") {
+        Some(path) => {
+            let keys = KeyFile::load(path)?;
+            let participants = records.iter().map(SnapshotParticipant::from).collect();
+            let snapshot = QueueSnapshot::sign(participants, &keys.public_key, &keys.secret_key);
+            serde_json::to_writer_pretty(writer, &snapshot)?;
+        }
+        None => export::write_records(&records, format, writer)?,
+    }
+
+    let general_schema = blockchain::Schema::new(&snapshot);
+    let block = general_schema.last_block();
+    eprintln!(
+        "Exported {} participants at height {}, state hash {}",
+        records.len(),
+        block.height(),
+        block.state_hash().to_hex()
+    );
+    Ok(())
+}
+
+fn main() {
+    let matches = App::new("iphone-queue-export")
+        .about("Exports participants of the iPhone queue with their histories")
+        .arg(
+            Arg::with_name("db")
+                .long("db")
+                .takes_value(true)
+                .required(true)
+                .help("Path to the node database directory"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .help("Path to the output file, stdout if omitted"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["jsonl", "csv"])
+                .default_value("jsonl")
+                .help("Output format"),
+        )
+        .arg(
+            Arg::with_name("sign")
+                .long("sign")
+                .takes_value(true)
+                .help("Path to the key file to write a signed snapshot with"),
+        )
+        .get_matches();
+
+    if let Err(e) = run(&matches) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,56 @@
+use exonum::crypto::{Hash, PublicKey};
+
+use super::{participant::Participant, proto, stats::QueueStats};
+
+/// Queue state recorded at the end of each block
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Checkpoint", serde_pb_convert)]
+pub struct Checkpoint {
+    /// Height of the block.
+    pub height: u64,
+    /// Queue is not empty.
+    pub has_head: bool,
+    /// Participant at the head of the queue, zero key if the queue is empty.
+    pub head: PublicKey,
+    /// Hash of the participants table.
+    pub participants_hash: Hash,
+    /// Queue statistics.
+    pub stats: QueueStats,
+}
+
+impl Checkpoint {
+    /// Creates new checkpoint
+    pub fn new(
+        height: u64,
+        head: Option<PublicKey>,
+        &participants_hash: &Hash,
+        stats: QueueStats,
+    ) -> Self {
+        Self {
+            height,
+            has_head: head.is_some(),
+            head: head.unwrap_or_else(|| PublicKey::new([0; 32])),
+            participants_hash,
+            stats,
+        }
+    }
+
+    /// Returns the participant at the head of the queue.
+    pub fn head(&self) -> Option<PublicKey> {
+        if self.has_head {
+            Some(self.head)
+        } else {
+            None
+        }
+    }
+}
+
+/// Participant state after a block
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::ParticipantVersion", serde_pb_convert)]
+pub struct ParticipantVersion {
+    /// Height of the block that changed the participant.
+    pub height: u64,
+    /// Participant after the change.
+    pub participant: Participant,
+}
@@ -0,0 +1,266 @@
+//! Command-line interface of the `iphone-queue-cli` operator tool.
+//!
+//! The arguments are defined here so they can be checked by tests without
+//! a running node, the binary only executes the parsed subcommands. The key
+//! file format is shared with `iphone-queue-export`.
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use exonum::crypto::{Hash, PublicKey, SecretKey};
+use hex::FromHex;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{fs, io::Write};
+
+/// Key pair stored in a local file, created by `iphone-queue-cli keygen`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyFile {
+    /// Public key.
+    pub public_key: PublicKey,
+    /// Secret key.
+    pub secret_key: SecretKey,
+}
+
+impl KeyFile {
+    /// Reads the key pair from the file.
+    pub fn load(path: &str) -> Result<Self, failure::Error> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Writes the key pair to a new file readable by the owner only, fails
+    /// if the file exists.
+    pub fn save(&self, path: &str) -> Result<(), failure::Error> {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Returns the argument with the address of the node HTTP API.
+pub fn node_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("node")
+        .long("node")
+        .takes_value(true)
+        .default_value("http://127.0.0.1:8200")
+        .help("Address of the node HTTP API")
+}
+
+/// Returns the argument with the path to the signing keys.
+pub fn keys_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("keys")
+        .long("keys")
+        .takes_value(true)
+        .required(true)
+        .help("Path to the key file used to sign transactions")
+}
+
+/// Returns the argument with the participant key.
+pub fn participant_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("participant")
+        .long("participant")
+        .takes_value(true)
+        .help("Hex public key of the participant, the signer key if omitted")
+}
+
+/// Returns the argument with the trusted validator keys, which can't be
+/// fetched from the node whose responses are verified.
+pub fn validator_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("validator")
+        .long("validator")
+        .takes_value(true)
+        .multiple(true)
+        .required(true)
+        .help("Trusted consensus key of a validator")
+}
+
+/// Parses the validator keys.
+pub fn validator_keys(matches: &ArgMatches) -> Result<Vec<PublicKey>, failure::Error> {
+    Ok(matches
+        .values_of("validator")
+        .into_iter()
+        .flatten()
+        .map(PublicKey::from_hex)
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Parses the numeric argument.
+pub fn number(matches: &ArgMatches, name: &str) -> Result<u64, failure::Error> {
+    let value = matches.value_of(name).unwrap();
+    value
+        .parse()
+        .map_err(|_| format_err!("Invalid {} `{}`, expected a number", name, value))
+}
+
+/// Parses the slot identifier.
+pub fn slot_id(matches: &ArgMatches) -> Result<Hash, failure::Error> {
+    Ok(Hash::from_hex(matches.value_of("slot").unwrap())?)
+}
+
+/// Returns the definition of the operator tool arguments.
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("iphone-queue-cli")
+        .about("Operator tool for the iPhone queue service")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("keygen")
+                .about("Generates a key pair and writes it to a file")
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Path to the key file"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Adds a participant to the queue")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(participant_arg())
+                .arg(validator_arg())
+                .arg(
+                    Arg::with_name("timestamp")
+                        .long("timestamp")
+                        .takes_value(true)
+                        .help("Queue timestamp, the current time if omitted"),
+                )
+                .arg(
+                    Arg::with_name("deposit")
+                        .long("deposit")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Deposit put down on joining"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("publish-slot")
+                .about("Publishes a pickup slot, signed by an operator")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(
+                    Arg::with_name("store")
+                        .long("store")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Store serving the slot"),
+                )
+                .arg(
+                    Arg::with_name("start")
+                        .long("start")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Start of the slot"),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .long("end")
+                        .takes_value(true)
+                        .required(true)
+                        .help("End of the slot"),
+                )
+                .arg(
+                    Arg::with_name("capacity")
+                        .long("capacity")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Number of participants the slot serves"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("book-slot")
+                .about("Books a pickup slot for the participant at the head of the queue")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(participant_arg())
+                .arg(validator_arg())
+                .arg(
+                    Arg::with_name("slot")
+                        .long("slot")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Hex identifier of the slot printed by `publish-slot`"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("buy")
+                .about("Records a purchase by the participant at the head of the queue")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(participant_arg())
+                .arg(validator_arg())
+                .arg(
+                    Arg::with_name("store")
+                        .long("store")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Store the phone is picked up at"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("remove")
+                .about("Removes a participant from the queue")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(participant_arg())
+                .arg(validator_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("pause")
+                .about("Pauses waiting of the participant owning the keys")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(validator_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("resume")
+                .about("Resumes waiting of the participant owning the keys")
+                .arg(node_arg())
+                .arg(keys_arg())
+                .arg(validator_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("status")
+                .about("Prints the status of a transaction")
+                .arg(node_arg())
+                .arg(
+                    Arg::with_name("hash")
+                        .required(true)
+                        .help("Hex hash of the transaction"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("first")
+                .about("Prints the key of the participant at the head of the queue")
+                .arg(node_arg())
+                .arg(validator_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("Prints the head, the next waiting and the recently served participants")
+                .arg(node_arg())
+                .arg(validator_arg())
+                .arg(
+                    Arg::with_name("next")
+                        .long("next")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("Number of waiting participants to print"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Fetches a participant and verifies the returned proofs")
+                .arg(node_arg())
+                .arg(validator_arg())
+                .arg(
+                    Arg::with_name("participant")
+                        .required(true)
+                        .help("Hex public key of the participant"),
+                ),
+        )
+}
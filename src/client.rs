@@ -0,0 +1,339 @@
+//! HTTP client for the queue service.
+//!
+//! Builds and signs queue transactions, submits them to a node, waits for
+//! their commitment and fetches participants verifying the returned proofs
+//! against the given validator set with `ParticipantInfo::verify`.
+use exonum::{
+    api::node::public::explorer::TransactionResponse,
+    crypto::{Hash, PublicKey, SecretKey},
+    messages::{self, RawTransaction, Signed},
+};
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    api::{
+        DisplayQuery, FirstParticipant, GetFirstQuery, ParticipantInfo, ParticipantQuery,
+        QueueDisplay,
+    },
+    participant::Participant,
+    transactions::{
+        Add, Attest, BookSlot, Buy, ErrorContext, ErrorDescription, Pause, PublishSlot, Remove,
+        Resume,
+    },
+    verify::ProofError,
+};
+
+/// Interval between transaction status requests in `wait_for_commit`.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Errors emitted by the client.
+#[derive(Debug, Fail)]
+pub enum ClientError {
+    /// HTTP request failed.
+    #[fail(display = "HTTP request failed: {}", _0)]
+    Http(#[cause] reqwest::Error),
+
+    /// Node returned an unexpected response.
+    #[fail(display = "Unexpected response: {}", _0)]
+    UnexpectedResponse(String),
+
+    /// Transaction wasn't committed in time.
+    #[fail(display = "Transaction wasn't committed in time")]
+    Timeout,
+
+    /// Transaction was committed with an execution error.
+    #[fail(display = "Transaction failed with code {}: {}", code, description)]
+    TransactionFailed {
+        /// Error code.
+        code: u8,
+        /// Error message.
+        description: String,
+        /// Error context.
+        context: ErrorContext,
+    },
+
+    /// Proof returned by the node is invalid.
+    #[fail(display = "Invalid proof: {}", _0)]
+    InvalidProof(#[cause] ProofError),
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(value: reqwest::Error) -> Self {
+        ClientError::Http(value)
+    }
+}
+
+impl From<ProofError> for ClientError {
+    fn from(value: ProofError) -> Self {
+        ClientError::InvalidProof(value)
+    }
+}
+
+/// Status of a submitted transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxStatus {
+    /// Node doesn't know the transaction.
+    Unknown,
+    /// Transaction is in the pool of unconfirmed transactions.
+    InPool,
+    /// Transaction is committed successfully.
+    Success,
+    /// Transaction is committed with an execution error.
+    Error {
+        /// Error code.
+        code: u8,
+        /// Error message.
+        description: String,
+        /// Error context.
+        context: ErrorContext,
+    },
+}
+
+/// Client of the queue service HTTP API.
+#[derive(Debug)]
+pub struct QueueClient {
+    base_url: String,
+    validator_keys: Vec<PublicKey>,
+    http: reqwest::Client,
+}
+
+impl QueueClient {
+    /// Creates a client for the node at `base_url`, e.g. `http://127.0.0.1:8200`.
+    ///
+    /// `validator_keys` are consensus keys of the validators used to check
+    /// block precommits.
+    pub fn new(base_url: &str, validator_keys: Vec<PublicKey>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            validator_keys,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn explorer_url(&self, path: &str) -> String {
+        format!("{}/api/explorer/{}", self.base_url, path)
+    }
+
+    fn service_url(&self, path: &str) -> String {
+        format!("{}/api/services/iphone_queue/{}", self.base_url, path)
+    }
+
+    /// Submits a signed transaction and returns its hash.
+    pub fn send(&self, tx: &Signed<RawTransaction>) -> Result<Hash, ClientError> {
+        let body = json!({ "tx_body": messages::to_hex_string(tx) });
+        let response: TransactionResponse = self
+            .http
+            .post(&self.explorer_url("v1/transactions"))
+            .json(&body)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        Ok(response.tx_hash)
+    }
+
+    /// Signs and submits an `Add` transaction.
+    pub fn add(
+        &self,
+        key: &PublicKey,
+        timestamp: u64,
+        deposit: u64,
+        signer: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&Add::sign_with_deposit(
+            signer.0, key, timestamp, deposit, signer.1,
+        ))
+    }
+
+    /// Signs and submits a `BookSlot` transaction.
+    pub fn book_slot(
+        &self,
+        key: &PublicKey,
+        slot: &Hash,
+        signer: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&BookSlot::sign(signer.0, key, slot, signer.1))
+    }
+
+    /// Signs and submits a `Buy` transaction.
+    pub fn buy(
+        &self,
+        key: &PublicKey,
+        store: &str,
+        signer: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&Buy::sign(signer.0, key, store, signer.1))
+    }
+
+    /// Signs and submits a `Remove` transaction.
+    pub fn remove(
+        &self,
+        key: &PublicKey,
+        signer: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&Remove::sign(signer.0, key, signer.1))
+    }
+
+    /// Signs and submits a `PublishSlot` transaction with the operator keys.
+    ///
+    /// The returned transaction hash is the identifier of the slot.
+    pub fn publish_slot(
+        &self,
+        store: &str,
+        start: u64,
+        end: u64,
+        capacity: u64,
+        operator: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&PublishSlot::sign(operator.0, store, start, end, capacity, operator.1))
+    }
+
+    /// Signs and submits an `Attest` transaction with the operator keys.
+    ///
+    /// A zero attestation or an empty order id is not attached.
+    pub fn attest(
+        &self,
+        key: &PublicKey,
+        attestation: &Hash,
+        order_id: &str,
+        operator: (&PublicKey, &SecretKey),
+    ) -> Result<Hash, ClientError> {
+        self.send(&Attest::sign(operator.0, key, attestation, order_id, operator.1))
+    }
+
+    /// Signs and submits a `Pause` transaction with the participant keys.
+    pub fn pause(&self, keys: (&PublicKey, &SecretKey)) -> Result<Hash, ClientError> {
+        self.send(&Pause::sign(keys.0, keys.0, keys.1))
+    }
+
+    /// Signs and submits a `Resume` transaction with the participant keys.
+    pub fn resume(&self, keys: (&PublicKey, &SecretKey)) -> Result<Hash, ClientError> {
+        self.send(&Resume::sign(keys.0, keys.0, keys.1))
+    }
+
+    /// Returns the status of the transaction.
+    pub fn tx_status(&self, tx_hash: &Hash) -> Result<TxStatus, ClientError> {
+        let response = self
+            .http
+            .get(&self.explorer_url("v1/transactions"))
+            .query(&[("hash", tx_hash.to_hex())])
+            .send()?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(TxStatus::Unknown);
+        }
+        let info: serde_json::Value = response.error_for_status()?.json()?;
+
+        match info["type"].as_str() {
+            Some("in-pool") => Ok(TxStatus::InPool),
+            Some("committed") => {
+                let status = &info["status"];
+                match status["type"].as_str() {
+                    Some("success") => Ok(TxStatus::Success),
+                    Some("error") => {
+                        let code = status["code"].as_u64().unwrap_or_default() as u8;
+                        let description = status["description"].as_str().unwrap_or_default();
+                        Ok(match ErrorDescription::parse(description) {
+                            Some(parsed) => TxStatus::Error {
+                                code,
+                                description: parsed.message,
+                                context: parsed.context,
+                            },
+                            None => TxStatus::Error {
+                                code,
+                                description: description.to_owned(),
+                                context: ErrorContext::default(),
+                            },
+                        })
+                    }
+                    _ => Err(ClientError::UnexpectedResponse(status.to_string())),
+                }
+            }
+            _ => Err(ClientError::UnexpectedResponse(info.to_string())),
+        }
+    }
+
+    /// Waits until the transaction is committed and checks its execution status.
+    pub fn wait_for_commit(&self, tx_hash: &Hash, timeout: Duration) -> Result<(), ClientError> {
+        let started = Instant::now();
+        loop {
+            match self.tx_status(tx_hash)? {
+                TxStatus::Success => return Ok(()),
+                TxStatus::Error {
+                    code,
+                    description,
+                    context,
+                } => {
+                    return Err(ClientError::TransactionFailed {
+                        code,
+                        description,
+                        context,
+                    })
+                }
+                TxStatus::Unknown | TxStatus::InPool => {}
+            }
+
+            if started.elapsed() >= timeout {
+                return Err(ClientError::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Returns the key of the participant at the head of the queue and
+    /// verifies the proof of the checkpoint recording it.
+    pub fn first(&self) -> Result<PublicKey, ClientError> {
+        let first: FirstParticipant = self
+            .http
+            .get(&self.service_url("v1/iphone_queue/get_first"))
+            .query(&GetFirstQuery::default())
+            .send()?
+            .error_for_status()?
+            .json()?;
+        first.verify(&self.validator_keys)?;
+        Ok(first.key)
+    }
+
+    /// Returns the "now serving" feed with `next` waiting participants and
+    /// verifies the proofs of the shown tickets.
+    pub fn display(&self, next: u64) -> Result<QueueDisplay, ClientError> {
+        let display: QueueDisplay = self
+            .http
+            .get(&self.service_url("v1/iphone_queue/display"))
+            .query(&DisplayQuery {
+                next: Some(next),
+                served: None,
+                height: None,
+            })
+            .send()?
+            .error_for_status()?
+            .json()?;
+        display.verify(&self.validator_keys)?;
+        Ok(display)
+    }
+
+    /// Fetches participant information without checking the proofs.
+    pub fn participant_info(&self, pub_key: &PublicKey) -> Result<ParticipantInfo, ClientError> {
+        let info = self
+            .http
+            .get(&self.service_url("v1/iphone_queue/info"))
+            .query(&ParticipantQuery::by_key(*pub_key))
+            .send()?
+            .error_for_status()?
+            .json()?;
+        Ok(info)
+    }
+
+    /// Fetches the participant and verifies the returned proofs.
+    ///
+    /// Returns `None` if the proof shows that there is no such participant.
+    pub fn participant(&self, pub_key: &PublicKey) -> Result<Option<Participant>, ClientError> {
+        let info = self.participant_info(pub_key)?;
+        let verified =
+            info.verify_query(&self.validator_keys, &ParticipantQuery::by_key(*pub_key))?;
+        Ok(verified.participant)
+    }
+}
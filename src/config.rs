@@ -0,0 +1,209 @@
+//! Service configuration supplied through the `NodeBuilder` commands.
+//!
+//! `generate-template --queue-config <path>` reads the configuration from a
+//! TOML file into the common template, `finalize` copies it into the node
+//! configuration and `ServiceFactory::make_service` passes it to the service,
+//! which stores it in the schema in `Service::initialize`. The stored
+//! configuration is a `ServiceTable`, so clients can check it against the
+//! block headers through the `config` endpoint.
+//!
+//! `generate-template --queue-snapshot <path>` adds a signed JSON snapshot of
+//! participants to the template, the snapshot is verified when the service
+//! is created and imported at genesis. The snapshot must be signed by one of
+//! the configured operators.
+use exonum::{
+    crypto::PublicKey,
+    helpers::fabric::{keys, Argument, CommandExtension, Context},
+    node::NodeConfig,
+};
+
+use std::fs;
+
+use super::{proto, snapshot::QueueSnapshot, SERVICE_NAME};
+
+/// Queue definition.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::QueueDefinition")]
+pub struct QueueDefinition {
+    /// Queue name.
+    pub name: String,
+    /// Stores serving the queue.
+    pub stores: Vec<String>,
+}
+
+/// Service configuration stored in the genesis block.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::ServiceConfig")]
+pub struct ServiceConfig {
+    /// Keys allowed to publish pickup slots and attest participants, nobody
+    /// if empty.
+    #[serde(default)]
+    pub operators: Vec<PublicKey>,
+    /// Queue definitions, slots may be published for any store if empty.
+    #[serde(default)]
+    pub queues: Vec<QueueDefinition>,
+    /// Number of phones in stock, unlimited if zero.
+    #[serde(default)]
+    pub stock: u64,
+    /// Number of blocks the participant at the head of the queue has to buy
+    /// before being removed, unlimited if zero.
+    #[serde(default)]
+    pub claim_window: u64,
+    /// Deposit a participant has to put down on joining, none if zero.
+    #[serde(default)]
+    pub deposit: u64,
+    /// Number of blocks a participant may stay paused before being removed,
+    /// unlimited if zero.
+    #[serde(default)]
+    pub max_pause: u64,
+}
+
+impl ServiceConfig {
+    /// Checks if the key belongs to an operator.
+    pub fn is_operator(&self, key: &PublicKey) -> bool {
+        self.operators.contains(key)
+    }
+
+    /// Checks if the store serves one of the queues.
+    pub fn is_known_store(&self, store: &str) -> bool {
+        self.queues.is_empty()
+            || self
+                .queues
+                .iter()
+                .any(|queue| queue.stores.iter().any(|s| s == store))
+    }
+
+    /// Checks if the pause started at `paused_at` is over at `height`.
+    pub fn pause_expired(&self, paused_at: u64, height: u64) -> bool {
+        self.max_pause != 0 && height >= paused_at.saturating_add(self.max_pause)
+    }
+
+    /// Checks if the claim of the head called at `called_at` is over at
+    /// `height`.
+    pub fn claim_expired(&self, called_at: u64, height: u64) -> bool {
+        self.claim_window != 0 && height >= called_at.saturating_add(self.claim_window)
+    }
+
+    /// Checks if the deposit is not below the configured size.
+    pub fn covers_deposit(&self, deposit: u64) -> bool {
+        deposit >= self.deposit
+    }
+
+    /// Verifies the snapshot and checks that it is signed by an operator.
+    pub fn verify_snapshot(&self, snapshot: &QueueSnapshot) -> Result<(), failure::Error> {
+        snapshot.verify()?;
+        if !self.operators.contains(&snapshot.signer) {
+            bail!(
+                "Snapshot is signed by {} which is not an operator",
+                snapshot.signer.to_hex()
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks if there are phones left after `bought` purchases.
+    pub fn in_stock(&self, bought: u64) -> bool {
+        self.stock == 0 || bought < self.stock
+    }
+}
+
+/// Genesis configuration of the service in the node configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct GenesisConfig {
+    /// Service configuration.
+    #[serde(flatten)]
+    pub config: ServiceConfig,
+    /// Snapshot of participants imported at genesis.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<QueueSnapshot>,
+}
+
+/// Name of the `generate-template` argument with the path to the configuration.
+const QUEUE_CONFIG_PATH: &str = "queue-config";
+/// Name of the `generate-template` argument with the path to the snapshot.
+const QUEUE_SNAPSHOT_PATH: &str = "queue-snapshot";
+
+/// `generate-template` command extension.
+#[derive(Debug)]
+pub struct GenerateCommonConfig;
+
+impl CommandExtension for GenerateCommonConfig {
+    fn args(&self) -> Vec<Argument> {
+        vec![
+            Argument::new_named(
+                QUEUE_CONFIG_PATH,
+                false,
+                "Path to the TOML file with the queue service configuration.",
+                None::<&str>,
+                QUEUE_CONFIG_PATH,
+                false,
+            ),
+            Argument::new_named(
+                QUEUE_SNAPSHOT_PATH,
+                false,
+                "Path to the JSON file with the signed snapshot of participants.",
+                None::<&str>,
+                QUEUE_SNAPSHOT_PATH,
+                false,
+            ),
+        ]
+    }
+
+    fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
+        let config: ServiceConfig = match context.arg::<String>(QUEUE_CONFIG_PATH).ok() {
+            Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+            None => ServiceConfig::default(),
+        };
+        let snapshot = match context.arg::<String>(QUEUE_SNAPSHOT_PATH).ok() {
+            Some(path) => {
+                let snapshot: QueueSnapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+                config.verify_snapshot(&snapshot)?;
+                Some(snapshot)
+            }
+            None => None,
+        };
+        let config = GenesisConfig { config, snapshot };
+
+        let mut values = context.get(keys::SERVICES_CONFIG).unwrap_or_default();
+        values.insert(SERVICE_NAME.to_owned(), toml::Value::try_from(config)?);
+        context.set(keys::SERVICES_CONFIG, values);
+        Ok(context)
+    }
+}
+
+/// `finalize` command extension.
+#[derive(Debug)]
+pub struct Finalize;
+
+impl CommandExtension for Finalize {
+    fn args(&self) -> Vec<Argument> {
+        Vec::new()
+    }
+
+    fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
+        let mut node_config: NodeConfig = context.get(keys::NODE_CONFIG)?;
+        let common_config = context.get(keys::COMMON_CONFIG)?;
+
+        if let Some(config) = common_config.services_config.get(SERVICE_NAME) {
+            node_config
+                .services_configs
+                .insert(SERVICE_NAME.to_owned(), config.clone());
+        }
+        context.set(keys::NODE_CONFIG, node_config);
+        Ok(context)
+    }
+}
+
+/// Reads the genesis configuration from the node configuration in the context
+/// and verifies the snapshot against the configured operators.
+pub fn from_context(context: &Context) -> Result<GenesisConfig, failure::Error> {
+    let node_config: NodeConfig = context.get(keys::NODE_CONFIG)?;
+    let genesis: GenesisConfig = match node_config.services_configs.get(SERVICE_NAME) {
+        Some(config) => config.clone().try_into()?,
+        None => GenesisConfig::default(),
+    };
+    if let Some(ref snapshot) = genesis.snapshot {
+        genesis.config.verify_snapshot(snapshot)?;
+    }
+    Ok(genesis)
+}
@@ -0,0 +1,76 @@
+use exonum::{
+    crypto::{Hash, PublicKey},
+    proto::ProtobufConvert,
+};
+
+use super::proto;
+
+/// Kind of a queue event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u32)]
+pub enum QueueEventKind {
+    /// Participant joined the queue.
+    Joined = 0,
+    /// Participant became the head of the queue.
+    Called = 1,
+    /// Participant bought a phone.
+    Bought = 2,
+    /// Participant was removed from the queue.
+    Removed = 3,
+    /// Participant was removed from the queue on expiration.
+    Expired = 4,
+    // 5 is skipped, the service has no transfers of the place to another key
+    /// Participant paused waiting.
+    Paused = 6,
+    /// Participant resumed waiting.
+    Resumed = 7,
+}
+
+impl ProtobufConvert for QueueEventKind {
+    type ProtoStruct = u32;
+
+    fn to_pb(&self) -> u32 {
+        *self as u32
+    }
+
+    fn from_pb(pb: u32) -> Result<Self, failure::Error> {
+        Ok(match pb {
+            0 => QueueEventKind::Joined,
+            1 => QueueEventKind::Called,
+            2 => QueueEventKind::Bought,
+            3 => QueueEventKind::Removed,
+            4 => QueueEventKind::Expired,
+            6 => QueueEventKind::Paused,
+            7 => QueueEventKind::Resumed,
+            _ => bail!("Unknown queue event kind {}", pb),
+        })
+    }
+}
+
+/// Queue event stored in the event log
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::QueueEvent", serde_pb_convert)]
+pub struct QueueEvent {
+    /// Kind of the event.
+    pub kind: QueueEventKind,
+    /// Participant the event refers to.
+    pub key: PublicKey,
+    /// Height of the block with the event.
+    pub height: u64,
+    /// `Hash` of the transaction caused the event, zero for events recorded
+    /// when the block is committed.
+    pub tx_hash: Hash,
+}
+
+impl QueueEvent {
+    /// Creates new event
+    pub fn new(kind: QueueEventKind, &key: &PublicKey, height: u64, &tx_hash: &Hash) -> Self {
+        Self {
+            kind,
+            key,
+            height,
+            tx_hash,
+        }
+    }
+}
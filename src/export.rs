@@ -0,0 +1,301 @@
+//! Export of the queue state for offline reports.
+//!
+//! Each participant is exported as one record with its status, timestamps
+//! and transactions history. Records include `history_hash`, so the export
+//! can be checked against a participant proof fetched from any node.
+//!
+//! With the `export` feature the node database can be opened read-only, so
+//! the export doesn't take the database lock and never writes to it.
+use exonum::{
+    blockchain,
+    crypto::{Hash, PublicKey},
+};
+use exonum_merkledb::IndexAccess;
+#[cfg(feature = "export")]
+use exonum_merkledb::{Iter, Iterator, Snapshot};
+
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+#[cfg(feature = "export")]
+use std::{path::Path, sync::Arc};
+
+use crate::{participant::Participant, schema::Schema};
+
+/// Format of the export file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// One JSON record per line.
+    JsonLines,
+    /// Comma-separated values with a header line, history hashes are
+    /// separated with `;`.
+    Csv,
+}
+
+impl FromStr for ExportFormat {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => bail!("Unknown export format {}, expected `jsonl` or `csv`", s),
+        }
+    }
+}
+
+/// Status of an exported participant.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    /// Participant is waiting in the queue.
+    Waiting,
+    /// Participant bought a phone.
+    Bought,
+    /// Participant was removed from the queue.
+    Removed,
+    /// Participant paused waiting.
+    Paused,
+}
+
+impl ExportStatus {
+    fn name(self) -> &'static str {
+        match self {
+            ExportStatus::Waiting => "waiting",
+            ExportStatus::Bought => "bought",
+            ExportStatus::Removed => "removed",
+            ExportStatus::Paused => "paused",
+        }
+    }
+}
+
+impl<'a> From<&'a Participant> for ExportStatus {
+    fn from(participant: &'a Participant) -> Self {
+        if participant.have_bought {
+            ExportStatus::Bought
+        } else if participant.removed {
+            ExportStatus::Removed
+        } else if participant.paused {
+            ExportStatus::Paused
+        } else {
+            ExportStatus::Waiting
+        }
+    }
+}
+
+/// Transaction or expiration event from the participant history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedTransaction {
+    /// `Hash` of the transaction or of the event.
+    pub tx_hash: Hash,
+    /// Height of the block with the transaction or the event.
+    pub height: Option<u64>,
+}
+
+/// Exported participant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportRecord {
+    /// Public key of the participant.
+    pub key: PublicKey,
+    /// Ticket number.
+    pub ticket: u64,
+    /// Queue timestamp.
+    pub timestamp: u64,
+    /// Status.
+    pub status: ExportStatus,
+    /// Height of the block the participant paused at, zero if not paused.
+    pub paused_at: u64,
+    /// Length of the transactions history.
+    pub history_len: u64,
+    /// `Hash` of the transactions history.
+    pub history_hash: Hash,
+    /// Transactions history.
+    pub history: Vec<ExportedTransaction>,
+}
+
+/// Collects records of all participants ordered by tickets.
+pub fn export_records<T>(view: T) -> Vec<ExportRecord>
+where
+    T: IndexAccess,
+{
+    let general_schema = blockchain::Schema::new(view.clone());
+    let schema = Schema::new(view);
+    let locations = general_schema.transactions_locations();
+
+    let mut records = schema
+        .participants()
+        .values()
+        .map(|participant| {
+            let history = schema
+                .participant_history(&participant.key)
+                .iter()
+                .map(|tx_hash| ExportedTransaction {
+                    height: locations
+                        .get(&tx_hash)
+                        .map(|location| location.block_height().0)
+                        .or_else(|| schema.history_events().get(&tx_hash).map(|x| x.height)),
+                    tx_hash,
+                })
+                .collect();
+
+            ExportRecord {
+                key: participant.key,
+                ticket: participant.ticket,
+                timestamp: participant.timestamp,
+                status: ExportStatus::from(&participant),
+                paused_at: participant.paused_at,
+                history_len: participant.history_len,
+                history_hash: participant.history_hash,
+                history,
+            }
+        })
+        .collect::<Vec<_>>();
+    records.sort_by_key(|record| record.ticket);
+    records
+}
+
+/// Writes records in the given format.
+pub fn write_records<W>(
+    records: &[ExportRecord],
+    format: ExportFormat,
+    mut writer: W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    match format {
+        ExportFormat::JsonLines => {
+            for record in records {
+                serde_json::to_writer(&mut writer, record)?;
+                writeln!(writer)?;
+            }
+        }
+        ExportFormat::Csv => {
+            writeln!(
+                writer,
+                "key,ticket,timestamp,status,history_len,history_hash,history"
+            )?;
+            for record in records {
+                let history = record
+                    .history
+                    .iter()
+                    .map(|tx| tx.tx_hash.to_hex())
+                    .collect::<Vec<_>>()
+                    .join(";");
+                writeln!(
+                    writer,
+                    "{},{},{},{},{},{},{}",
+                    record.key.to_hex(),
+                    record.ticket,
+                    record.timestamp,
+                    record.status.name(),
+                    record.history_len,
+                    record.history_hash.to_hex(),
+                    history
+                )?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+/// Opens the RocksDB database of a node read-only.
+///
+/// The snapshot sees the state at the time it's opened, blocks committed by
+/// a running node after that aren't visible. The database is never created
+/// or modified.
+#[cfg(feature = "export")]
+pub fn open_read_only<P>(path: P) -> Result<Box<dyn Snapshot>, failure::Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if !path.is_dir() {
+        bail!("Database directory {} doesn't exist", path.display());
+    }
+    let options = rocksdb::Options::default();
+    let column_families = rocksdb::DB::list_cf(&options, path)?;
+    let db = rocksdb::DB::open_cf_for_read_only(&options, path, &column_families, false)?;
+    Ok(Box::new(ReadOnlySnapshot { db: Arc::new(db) }))
+}
+
+/// Snapshot over a database opened read-only.
+#[cfg(feature = "export")]
+struct ReadOnlySnapshot {
+    db: Arc<rocksdb::DB>,
+}
+
+#[cfg(feature = "export")]
+impl Snapshot for ReadOnlySnapshot {
+    fn get(&self, name: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let cf = self.db.cf_handle(name)?;
+        self.db
+            .get_cf(cf, key)
+            .unwrap_or_else(|e| panic!("Unable to read the database: {}", e))
+            .map(|value| value.to_vec())
+    }
+
+    fn iter<'a>(&'a self, name: &str, from: &[u8]) -> Iter<'a> {
+        let iter = self.db.cf_handle(name).map(|cf| {
+            let mut iter = self
+                .db
+                .raw_iterator_cf(cf)
+                .unwrap_or_else(|e| panic!("Unable to read the database: {}", e));
+            iter.seek(from);
+            iter
+        });
+        Box::new(ReadOnlyIter {
+            iter,
+            current: None,
+            returned: None,
+        })
+    }
+}
+
+/// Iterator over a column family of a database opened read-only.
+#[cfg(feature = "export")]
+struct ReadOnlyIter<'a> {
+    iter: Option<rocksdb::DBRawIterator<'a>>,
+    /// Entry at the iterator position, read by `peek`.
+    current: Option<(Vec<u8>, Vec<u8>)>,
+    /// Entry returned by the last `next`.
+    returned: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+#[cfg(feature = "export")]
+impl<'a> ReadOnlyIter<'a> {
+    /// Reads the entry at the iterator position into `current`.
+    fn load(&mut self) {
+        if self.current.is_none() {
+            self.current = self.iter.as_ref().and_then(|iter| {
+                if iter.valid() {
+                    Some((iter.key()?, iter.value()?))
+                } else {
+                    None
+                }
+            });
+        }
+    }
+}
+
+#[cfg(feature = "export")]
+impl<'a> Iterator for ReadOnlyIter<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        self.load();
+        self.returned = self.current.take();
+        if self.returned.is_some() {
+            if let Some(iter) = self.iter.as_mut() {
+                iter.next();
+            }
+        }
+        let (key, value) = self.returned.as_ref()?;
+        Some((key, value))
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        self.load();
+        let (key, value) = self.current.as_ref()?;
+        Some((key, value))
+    }
+}
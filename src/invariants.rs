@@ -0,0 +1,129 @@
+//! State transition rules for participants.
+//!
+//! Every transaction checks its preconditions here instead of relying on
+//! the way `Schema::first_participant` filters the queue, and every write
+//! of a `Participant` in the schema is validated with `check_transition`.
+use crate::{participant::Participant, transactions::Error};
+
+/// Checks that a participant can be added.
+pub fn check_add(existing: Option<&Participant>) -> Result<(), Error> {
+    match existing {
+        Some(_) => Err(Error::ParticipantAlreadyExists),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the participant is still waiting in the queue.
+pub fn check_waiting(participant: &Participant) -> Result<(), Error> {
+    if participant.have_bought {
+        return Err(Error::ParticipantAlreadyBought);
+    }
+    if participant.removed {
+        return Err(Error::ParticipantAlreadyRemoved);
+    }
+    Ok(())
+}
+
+/// Checks that the participant is waiting and is at the head of the queue.
+pub fn check_first(participant: &Participant, first: Option<&Participant>) -> Result<(), Error> {
+    check_waiting(participant)?;
+    match first {
+        Some(first) if first.key == participant.key => Ok(()),
+        _ => Err(Error::ParticipantIsNotFirst),
+    }
+}
+
+/// Checks that the participant can book a slot.
+pub fn check_book_slot(
+    participant: &Participant,
+    first: Option<&Participant>,
+    already_booked: bool,
+) -> Result<(), Error> {
+    check_waiting(participant)?;
+    if already_booked {
+        return Err(Error::SlotAlreadyBooked);
+    }
+    check_first(participant, first)
+}
+
+/// Checks that the participant can buy a phone.
+pub fn check_buy(participant: &Participant, first: Option<&Participant>) -> Result<(), Error> {
+    check_first(participant, first)
+}
+
+/// Checks that the participant can be removed from the queue.
+pub fn check_remove(participant: &Participant) -> Result<(), Error> {
+    if participant.removed {
+        return Err(Error::ParticipantAlreadyRemoved);
+    }
+    if participant.have_bought {
+        return Err(Error::ParticipantAlreadyBought);
+    }
+    Ok(())
+}
+
+/// Checks that the participant can pause waiting.
+pub fn check_pause(participant: &Participant) -> Result<(), Error> {
+    check_waiting(participant)?;
+    if participant.paused {
+        return Err(Error::ParticipantAlreadyPaused);
+    }
+    Ok(())
+}
+
+/// Checks that the participant can resume waiting.
+pub fn check_resume(participant: &Participant) -> Result<(), Error> {
+    check_waiting(participant)?;
+    if !participant.paused {
+        return Err(Error::ParticipantNotPaused);
+    }
+    Ok(())
+}
+
+/// Checks that `after` is a valid successor of `before`.
+///
+/// The key, the timestamp and the ticket never change, a participant can't
+/// be both served and removed, only waiting participants may be paused,
+/// final statuses are never reverted and each transition appends exactly
+/// one record to the history.
+pub fn check_transition(before: &Participant, after: &Participant) -> Result<(), Error> {
+    let valid = same_place(before, after)
+        && (!before.have_bought || after.have_bought)
+        && (!before.removed || after.removed)
+        && after.history_len == before.history_len + 1;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidTransition)
+    }
+}
+
+/// Checks that `after` is the waiting participant `before` removed on
+/// expiration of the pause or of the claim window.
+///
+/// Expiration is not caused by a transaction, the hash of the `Expired`
+/// event is appended to the history instead.
+pub fn check_expiration(before: &Participant, after: &Participant) -> Result<(), Error> {
+    let valid = same_place(before, after)
+        && !before.have_bought
+        && !before.removed
+        && after.removed
+        && after.history_len == before.history_len + 1
+        && after.history_hash != before.history_hash;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidTransition)
+    }
+}
+
+/// Checks the fields that never change and the consistency of the statuses.
+fn same_place(before: &Participant, after: &Participant) -> bool {
+    before.key == after.key
+        && before.timestamp == after.timestamp
+        && before.ticket == after.ticket
+        && !(after.have_bought && after.removed)
+        && !(after.paused && (after.have_bought || after.removed))
+}
@@ -7,6 +7,8 @@
     bare_trait_objects
 )]
 
+#[cfg(any(feature = "cli", feature = "export"))]
+extern crate clap;
 extern crate exonum;
 #[macro_use]
 extern crate exonum_derive;
@@ -15,31 +17,80 @@ extern crate failure;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate hex;
 extern crate protobuf;
+#[cfg(feature = "client")]
+extern crate reqwest;
+#[cfg(feature = "testing")]
+extern crate exonum_testkit;
+#[cfg(feature = "testing")]
+extern crate rand;
+#[cfg(feature = "export")]
+extern crate rocksdb;
+#[cfg_attr(any(feature = "client", feature = "testing"), macro_use)]
+extern crate serde_json;
+extern crate toml;
 
 /// API
 pub mod api;
+/// Per-block queue checkpoints
+pub mod checkpoint;
+/// Operator tool arguments
+#[cfg(any(feature = "cli", feature = "export"))]
+pub mod cli;
+/// HTTP client
+#[cfg(feature = "client")]
+pub mod client;
+/// Service configuration
+pub mod config;
+/// Queue event log records
+pub mod event;
+/// Queue state export
+pub mod export;
+/// Participant state transition rules
+pub mod invariants;
+/// Stored data layout versions and migrations
+pub mod migration;
+/// Transaction outcome struct
+pub mod outcome;
 /// Pipe type struct
 pub mod participant;
 /// PB structures
 pub mod proto;
 /// Schema
 pub mod schema;
+/// Pickup slot struct
+pub mod slot;
+/// Genesis snapshot of participants
+pub mod snapshot;
+/// Queue statistics struct
+pub mod stats;
+/// Testkit helpers
+#[cfg(feature = "testing")]
+pub mod testing;
 /// Transactions
 pub mod transactions;
+/// Queue updates feed
+pub mod updates;
+/// Proof verification
+pub mod verify;
 
-use exonum_merkledb::Snapshot;
+use exonum_merkledb::{Fork, Snapshot};
 
 use exonum::{
     api::ServiceApiBuilder,
-    blockchain::{self, Transaction, TransactionSet},
+    blockchain::{self, ServiceContext, Transaction, TransactionSet},
     crypto::Hash,
-    helpers::fabric::{self, Context},
+    helpers::fabric::{self, Command, CommandExtension, CommandName, Context},
     messages::RawTransaction,
 };
+use serde_json::Value;
 
+use config::ServiceConfig;
 use schema::Schema;
+use snapshot::QueueSnapshot;
 use transactions::ParticipantTransactions;
+use updates::UpdateFeed;
 
 /// Unique service id
 pub const SERVICE_ID: u16 = 10;
@@ -48,7 +99,30 @@ const SERVICE_NAME: &str = "iphone_queue";
 
 /// Exonum `Service` implementation.
 #[derive(Default, Debug)]
-pub struct Service;
+pub struct Service {
+    config: ServiceConfig,
+    snapshot: Option<QueueSnapshot>,
+    updates: UpdateFeed,
+}
+
+impl Service {
+    /// Creates the service with the given genesis configuration.
+    pub fn new(config: ServiceConfig) -> Self {
+        Self {
+            config,
+            snapshot: None,
+            updates: UpdateFeed::default(),
+        }
+    }
+
+    /// Imports participants of the snapshot at genesis.
+    pub fn with_snapshot(self, snapshot: QueueSnapshot) -> Self {
+        Self {
+            snapshot: Some(snapshot),
+            ..self
+        }
+    }
+}
 
 impl blockchain::Service for Service {
     fn service_id(&self) -> u16 {
@@ -68,8 +142,37 @@ impl blockchain::Service for Service {
         ParticipantTransactions::tx_from_raw(raw).map(Into::into)
     }
 
+    fn initialize(&self, fork: &Fork) -> Value {
+        let mut schema = Schema::new(fork);
+        schema.set_config(self.config.clone());
+        schema.set_schema_version(migration::CURRENT_VERSION);
+        if let Some(ref snapshot) = self.snapshot {
+            self.config
+                .verify_snapshot(snapshot)
+                .expect("Invalid queue snapshot");
+            schema.import_snapshot(snapshot);
+        }
+        serde_json::to_value(&self.config).unwrap()
+    }
+
+    fn before_commit(&self, fork: &Fork) {
+        let mut schema = Schema::new(fork);
+        // blocks without successful transactions are migrated here, see `migration`
+        migration::migrate(&mut schema);
+        schema.record_transaction_outcomes();
+        schema.expire_pauses();
+        schema.expire_claim();
+        schema.record_checkpoint();
+    }
+
+    fn after_commit(&self, context: &ServiceContext) {
+        self.updates.on_commit(context.snapshot());
+    }
+
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         api::PublicApi::wire(builder);
+        api::PrivateApi::wire(builder);
+        self.updates.wire(builder);
     }
 }
 
@@ -82,7 +185,23 @@ impl fabric::ServiceFactory for ServiceFactory {
         SERVICE_NAME
     }
 
-    fn make_service(&mut self, _: &Context) -> Box<dyn blockchain::Service> {
-        Box::new(Service)
+    fn command(&mut self, command: CommandName) -> Option<Box<dyn CommandExtension>> {
+        Some(match command {
+            v if v == fabric::GenerateCommonConfig.name() => {
+                Box::new(config::GenerateCommonConfig)
+            }
+            v if v == fabric::Finalize.name() => Box::new(config::Finalize),
+            _ => return None,
+        })
+    }
+
+    fn make_service(&mut self, context: &Context) -> Box<dyn blockchain::Service> {
+        let genesis = config::from_context(context)
+            .expect("Invalid iphone_queue service configuration");
+        let service = Service::new(genesis.config);
+        Box::new(match genesis.snapshot {
+            Some(snapshot) => service.with_snapshot(snapshot),
+            None => service,
+        })
     }
 }
@@ -0,0 +1,119 @@
+//! Versions of the stored data layout and migrations between them.
+//!
+//! The version of the layout is stored in `Schema::schema_version`. Chains
+//! created before the version marker was introduced have no version stored
+//! and are treated as version `0`. New chains start at `CURRENT_VERSION`
+//! from `Service::initialize`, existing ones are migrated in the first block
+//! after the upgrade, so every node applies the same changes at the same
+//! height.
+//!
+//! Every transaction migrates the data before reading it, so the
+//! transactions of the first block after the upgrade are executed on the
+//! current layout. The changes of a failed transaction are rolled back with
+//! the migration, which is then applied by the next one, or by
+//! `before_commit` if no transaction of the block succeeds.
+//!
+//! Layout versions:
+//!
+//! - `0`: the baseline layout, `Participant` without the ticket number and
+//!   no indices other than the participants and their histories.
+//! - `1`: participants are assigned sequential tickets indexed in
+//!   `Schema::tickets`, with their versions, the served list and the queue
+//!   statistics.
+//!
+//! Indices added since the baseline are written by the transactions as they
+//! are, so only the data derived from the baseline participants needs a
+//! migration. Fields added with defaults meaning the old behaviour don't
+//! need a new version: the pause state and the deposit of `Participant` are
+//! decoded from version `0` records as not paused without a deposit, which
+//! is their actual state.
+use exonum_merkledb::IndexAccess;
+
+use crate::{participant::Participant, schema::Schema};
+
+/// Version of the layout written by this version of the service.
+pub const CURRENT_VERSION: u64 = 1;
+
+/// Migration to the next layout version.
+pub type Migration<T> = fn(&mut Schema<T>);
+
+/// Returns the migration from the given version to the next one.
+pub fn migration<T>(from: u64) -> Option<Migration<T>>
+where
+    T: IndexAccess,
+{
+    match from {
+        0 => Some(migrate_baseline),
+        _ => None,
+    }
+}
+
+/// Migrates the stored data to `CURRENT_VERSION`.
+///
+/// Returns the version the data was migrated from, `None` if the data is
+/// up to date.
+pub fn migrate<T>(schema: &mut Schema<T>) -> Option<u64>
+where
+    T: IndexAccess,
+{
+    let from = schema.schema_version();
+    if from >= CURRENT_VERSION {
+        return None;
+    }
+
+    for version in from..CURRENT_VERSION {
+        let migration = migration(version)
+            .unwrap_or_else(|| panic!("No migration from schema version {}", version));
+        migration(schema);
+        schema.set_schema_version(version + 1);
+    }
+    Some(from)
+}
+
+/// Migrates participants stored with the baseline layout.
+///
+/// Tickets are assigned after the last ticket in the queue order, so the
+/// relative order of participants with equal timestamps becomes fixed. The
+/// baseline doesn't record the order of purchases, so participants who
+/// bought a phone are added to the served list in the order of tickets.
+fn migrate_baseline<T>(schema: &mut Schema<T>)
+where
+    T: IndexAccess,
+{
+    let mut legacy = schema
+        .participants()
+        .values()
+        .filter(|participant| participant.ticket == 0)
+        .collect::<Vec<_>>();
+    legacy.sort_by(|x, y| {
+        x.timestamp
+            .cmp(&y.timestamp)
+            .then_with(|| x.key.as_ref().cmp(y.key.as_ref()))
+    });
+
+    let mut ticket = schema.last_ticket();
+    for participant in legacy {
+        ticket += 1;
+        let migrated = Participant {
+            ticket,
+            ..participant
+        };
+        schema.tickets().put(&ticket, migrated.key);
+        if migrated.have_bought {
+            schema.served().push(migrated.key);
+        }
+        schema.update_stats(|stats| {
+            let stats = stats.add();
+            if migrated.have_bought {
+                stats.buy()
+            } else if migrated.removed {
+                stats.remove()
+            } else {
+                stats
+            }
+        });
+        schema.record_version(&migrated);
+        schema.participants().put(&migrated.key, migrated);
+    }
+    schema.set_last_ticket(ticket);
+}
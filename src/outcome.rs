@@ -0,0 +1,83 @@
+use exonum::{
+    blockchain::{TransactionError, TransactionErrorType},
+    crypto::Hash,
+};
+
+use super::{proto, transactions::ErrorDescription};
+
+/// Outcome of a transaction referencing a participant
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::TransactionOutcome", serde_pb_convert)]
+pub struct TransactionOutcome {
+    /// `Hash` of the transaction.
+    pub tx_hash: Hash,
+    /// Height of the block with the transaction.
+    pub height: u64,
+    /// Position of the transaction in the block.
+    pub position: u64,
+    /// Transaction is executed successfully.
+    pub success: bool,
+    /// Transaction execution panicked.
+    pub panic: bool,
+    /// Error code, zero on success and panic.
+    pub code: u32,
+    /// Error description.
+    pub description: String,
+}
+
+/// Execution status of a transaction.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    /// Transaction is executed successfully.
+    Success,
+    /// Transaction returned an execution error.
+    Error {
+        /// Error code.
+        code: u8,
+        /// Parsed error description, `None` if the description is not
+        /// emitted by the service.
+        error: Option<ErrorDescription>,
+    },
+    /// Transaction execution panicked.
+    Panic {
+        /// Panic message.
+        description: String,
+    },
+}
+
+impl OutcomeStatus {
+    /// Creates the status from the execution result stored by the blockchain.
+    pub fn from_result(result: &Result<(), TransactionError>) -> Self {
+        match result {
+            Ok(()) => OutcomeStatus::Success,
+            Err(error) => match error.error_type() {
+                TransactionErrorType::Panic => OutcomeStatus::Panic {
+                    description: error.description().unwrap_or_default().to_owned(),
+                },
+                TransactionErrorType::Code(code) => OutcomeStatus::Error {
+                    code,
+                    error: error.description().and_then(ErrorDescription::parse),
+                },
+            },
+        }
+    }
+}
+
+impl TransactionOutcome {
+    /// Returns the execution status.
+    pub fn status(&self) -> OutcomeStatus {
+        if self.success {
+            OutcomeStatus::Success
+        } else if self.panic {
+            OutcomeStatus::Panic {
+                description: self.description.clone(),
+            }
+        } else {
+            OutcomeStatus::Error {
+                code: self.code as u8,
+                error: ErrorDescription::parse(&self.description),
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use super::proto;
 use exonum::crypto::{Hash, PublicKey};
 
+/// Prefix of the ticket labels.
+pub const TICKET_PREFIX: &str = "A";
+
 /// Stores information about a participant
 #[derive(Clone, Debug, ProtobufConvert)]
 #[exonum(pb = "proto::Participant", serde_pb_convert)]
@@ -17,6 +20,14 @@ pub struct Participant {
     pub history_len: u64,
     /// `Hash` of the transactions history.
     pub history_hash: Hash,
+    /// Sequential ticket number in the queue.
+    pub ticket: u64,
+    /// Participant paused waiting and is skipped at the head of the queue.
+    pub paused: bool,
+    /// Height of the block the participant paused at.
+    pub paused_at: u64,
+    /// Deposit put down on joining.
+    pub deposit: u64,
 }
 
 impl Participant {
@@ -28,6 +39,7 @@ impl Participant {
         removed: bool,
         history_len: u64,
         &history_hash: &Hash,
+        ticket: u64,
     ) -> Self {
         Self {
             key,
@@ -36,22 +48,34 @@ impl Participant {
             removed,
             history_len,
             history_hash,
+            ticket,
+            paused: false,
+            paused_at: 0,
+            deposit: 0,
         }
     }
+
+    /// Checks if the participant is waiting and not paused.
+    pub fn is_active(&self) -> bool {
+        !self.have_bought && !self.removed && !self.paused
+    }
+
+    /// Returns the ticket number as shown to customers, e.g. `A-042`.
+    pub fn ticket_label(&self) -> String {
+        format!("{}-{:03}", TICKET_PREFIX, self.ticket)
+    }
     
     /// buy
     pub fn buy(
         self,
         &history_hash: &Hash
     ) -> Self {
-        Self::new(
-            &self.key,
-            self.timestamp,
-            true,
-            self.removed,
-            self.history_len + 1,
-            &history_hash
-        )
+        Self {
+            have_bought: true,
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
     }
     
     /// remove
@@ -59,13 +83,70 @@ impl Participant {
         self,
         &history_hash: &Hash
     ) -> Self {
-        Self::new(
-            &self.key,
-            self.timestamp,
-            self.have_bought,
-            true,
-            self.history_len + 1,
-            &history_hash
-        )
+        Self {
+            removed: true,
+            paused: false,
+            paused_at: 0,
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
+    }
+
+    /// Records a new history entry without changing the status
+    pub fn touch(
+        self,
+        &history_hash: &Hash
+    ) -> Self {
+        Self {
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
+    }
+
+    /// Pauses waiting at the given height
+    pub fn pause(
+        self,
+        height: u64,
+        &history_hash: &Hash
+    ) -> Self {
+        Self {
+            paused: true,
+            paused_at: height,
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
+    }
+
+    /// Resumes waiting
+    pub fn resume(
+        self,
+        &history_hash: &Hash
+    ) -> Self {
+        Self {
+            paused: false,
+            paused_at: 0,
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
+    }
+
+    /// Removes the participant whose pause or claim window is over, the
+    /// expiration event is appended to the history
+    pub fn expire(
+        self,
+        &history_hash: &Hash
+    ) -> Self {
+        Self {
+            removed: true,
+            paused: false,
+            paused_at: 0,
+            history_len: self.history_len + 1,
+            history_hash,
+            ..self
+        }
     }
 }
@@ -1,7 +1,11 @@
 #![allow(bare_trait_objects)]
 #![allow(renamed_and_removed_lints)]
 
-pub use self::iphone_queue::{Add, Buy, Participant, Remove};
+pub use self::iphone_queue::{
+    Add, Attest, BookSlot, Buy, Checkpoint, Participant, ParticipantVersion, Pause, PublishSlot,
+    QueueDefinition, QueueEvent, QueueStats, Remove, Resume, ServiceConfig, Slot, SnapshotInfo,
+    SnapshotParticipant, TransactionOutcome,
+};
 
 include!(concat!(env!("OUT_DIR"), "/protobuf_mod.rs"));
 
@@ -1,13 +1,166 @@
 //! Iphone queue database schema.
-use crate::participant::Participant;
-use exonum::crypto::{Hash, PublicKey};
-use exonum_merkledb::{IndexAccess, ObjectHash, ProofListIndex, ProofMapIndex};
+use crate::{
+    checkpoint::{Checkpoint, ParticipantVersion},
+    config::ServiceConfig,
+    event::{QueueEvent, QueueEventKind},
+    invariants,
+    outcome::TransactionOutcome,
+    participant::Participant,
+    slot::Slot,
+    snapshot::{QueueSnapshot, SnapshotInfo},
+    stats::QueueStats,
+    transactions::ParticipantTransactions,
+    SERVICE_ID,
+};
+use exonum::{
+    blockchain::{self, TransactionErrorType, TransactionSet},
+    crypto::{Hash, PublicKey},
+    helpers::Height,
+};
+use exonum_merkledb::{
+    Entry, IndexAccess, ListIndex, MapIndex, ObjectHash, ProofListIndex, ProofMapIndex,
+};
 use std::cmp::Ordering;
 
 /// Pipe types table name
 pub const PARTICIPANT_TYPES_TABLE: &str = "iphone_queue.participant";
 /// Pipe type history table name
 pub const PARTICIPANT_HISTORY_TABLE: &str = "iphone_queue.participant.history";
+/// Pickup slots table name
+pub const SLOTS_TABLE: &str = "iphone_queue.slots";
+/// Booked slots table name
+pub const PARTICIPANT_SLOTS_TABLE: &str = "iphone_queue.participant.slot";
+/// Queue statistics entry name
+pub const QUEUE_STATS_ENTRY: &str = "iphone_queue.stats";
+/// Last assigned ticket number entry name
+pub const TICKET_COUNTER_ENTRY: &str = "iphone_queue.ticket_counter";
+/// Ticket numbers table name
+pub const TICKETS_TABLE: &str = "iphone_queue.tickets";
+/// Attestations table name
+pub const ATTESTATIONS_TABLE: &str = "iphone_queue.attestations";
+/// External order ids table name
+pub const ORDER_IDS_TABLE: &str = "iphone_queue.order_ids";
+/// Attestations by participant table name
+pub const PARTICIPANT_ATTESTATIONS_TABLE: &str = "iphone_queue.participant.attestation";
+/// External order ids by participant table name
+pub const PARTICIPANT_ORDER_IDS_TABLE: &str = "iphone_queue.participant.order_id";
+/// Served participants table name
+pub const SERVED_TABLE: &str = "iphone_queue.served_keys";
+/// Transaction outcomes table name
+pub const PARTICIPANT_TRANSACTIONS_TABLE: &str = "iphone_queue.participant.transactions";
+/// Queue checkpoints table name
+pub const CHECKPOINTS_TABLE: &str = "iphone_queue.checkpoints";
+/// Participant versions table name
+pub const PARTICIPANT_VERSIONS_TABLE: &str = "iphone_queue.participant.version_list";
+/// Root hashes of the participant versions table name
+pub const VERSIONS_TABLE: &str = "iphone_queue.versions";
+/// Height the head of the queue was called at entry name
+pub const CALLED_AT_ENTRY: &str = "iphone_queue.called_at";
+/// Queue events table name
+pub const EVENTS_TABLE: &str = "iphone_queue.events";
+/// Events from participant histories table name
+pub const HISTORY_EVENTS_TABLE: &str = "iphone_queue.history_events";
+/// Paused participants by the height their pause expires at table name
+pub const PAUSE_DEADLINES_TABLE: &str = "iphone_queue.pause_deadlines";
+/// Service configuration entry name
+pub const CONFIG_ENTRY: &str = "iphone_queue.config";
+/// Genesis snapshot entry name
+pub const SNAPSHOT_ENTRY: &str = "iphone_queue.snapshot";
+/// Stored data layout version entry name
+pub const SCHEMA_VERSION_ENTRY: &str = "iphone_queue.schema_version";
+
+/// Errors of the schema updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaError {
+    /// Slot has no free places.
+    SlotIsFull,
+    /// Participant update violates the state transition invariants.
+    InvalidTransition,
+}
+
+/// Merkelized service tables aggregated into the blockchain `state_hash`.
+///
+/// The discriminant of each variant is the index of the table in
+/// `Schema::state_hash` and the table index to pass to
+/// `get_proof_to_service_table`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTable {
+    /// `Schema::participants`.
+    Participants = 0,
+    /// `Schema::slots`.
+    Slots = 1,
+    /// `Schema::participant_slots`.
+    ParticipantSlots = 2,
+    /// `Schema::queue_stats`.
+    QueueStats = 3,
+    /// `Schema::tickets`.
+    Tickets = 4,
+    /// `Schema::attestations`.
+    Attestations = 5,
+    /// `Schema::order_ids`.
+    OrderIds = 6,
+    /// `Schema::checkpoints`.
+    Checkpoints = 7,
+    /// `Schema::events`.
+    Events = 8,
+    /// `Schema::snapshot`.
+    Snapshot = 9,
+    /// `Schema::served`.
+    Served = 10,
+    /// `Schema::config`.
+    Config = 11,
+    /// `Schema::versions`.
+    Versions = 12,
+}
+
+impl ServiceTable {
+    /// All registered tables in the `state_hash` order.
+    pub const ALL: [ServiceTable; 13] = [
+        ServiceTable::Participants,
+        ServiceTable::Slots,
+        ServiceTable::ParticipantSlots,
+        ServiceTable::QueueStats,
+        ServiceTable::Tickets,
+        ServiceTable::Attestations,
+        ServiceTable::OrderIds,
+        ServiceTable::Checkpoints,
+        ServiceTable::Events,
+        ServiceTable::Snapshot,
+        ServiceTable::Served,
+        ServiceTable::Config,
+        ServiceTable::Versions,
+    ];
+
+    /// Returns the index of the table in the service `state_hash`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the name of the underlying index.
+    pub fn name(self) -> &'static str {
+        match self {
+            ServiceTable::Participants => PARTICIPANT_TYPES_TABLE,
+            ServiceTable::Slots => SLOTS_TABLE,
+            ServiceTable::ParticipantSlots => PARTICIPANT_SLOTS_TABLE,
+            ServiceTable::QueueStats => QUEUE_STATS_ENTRY,
+            ServiceTable::Tickets => TICKETS_TABLE,
+            ServiceTable::Attestations => ATTESTATIONS_TABLE,
+            ServiceTable::OrderIds => ORDER_IDS_TABLE,
+            ServiceTable::Checkpoints => CHECKPOINTS_TABLE,
+            ServiceTable::Events => EVENTS_TABLE,
+            ServiceTable::Snapshot => SNAPSHOT_ENTRY,
+            ServiceTable::Served => SERVED_TABLE,
+            ServiceTable::Config => CONFIG_ENTRY,
+            ServiceTable::Versions => VERSIONS_TABLE,
+        }
+    }
+
+    /// Returns the key of the table in the proof to the service tables.
+    pub fn key(self) -> Hash {
+        blockchain::Blockchain::service_table_unique_key(SERVICE_ID, self.index())
+    }
+}
 
 /// Database schema.
 #[derive(Debug)]
@@ -45,56 +198,363 @@ where
         self.participants().get(pub_key)
     }
 
+    /// Returns `ProofMapIndex` with pickup slots.
+    pub fn slots(&self) -> ProofMapIndex<T, Hash, Slot> {
+        ProofMapIndex::new(SLOTS_TABLE, self.view.clone())
+    }
+
+    /// Returns `ProofMapIndex` with slots booked by participants.
+    pub fn participant_slots(&self) -> ProofMapIndex<T, PublicKey, Hash> {
+        ProofMapIndex::new(PARTICIPANT_SLOTS_TABLE, self.view.clone())
+    }
+
+    /// Returns slot for the given hash.
+    pub fn slot(&self, id: &Hash) -> Option<Slot> {
+        self.slots().get(id)
+    }
+
+    /// Returns slot booked by the participant with the given public key.
+    pub fn participant_slot(&self, pub_key: &PublicKey) -> Option<Slot> {
+        self.participant_slots()
+            .get(pub_key)
+            .and_then(|id| self.slot(&id))
+    }
+
+    fn ticket_counter(&self) -> Entry<T, u64> {
+        Entry::new(TICKET_COUNTER_ENTRY, self.view.clone())
+    }
+
+    /// Returns the last assigned ticket number.
+    pub fn last_ticket(&self) -> u64 {
+        self.ticket_counter().get().unwrap_or_default()
+    }
+
+    /// Stores the last assigned ticket number.
+    pub(crate) fn set_last_ticket(&mut self, ticket: u64) {
+        self.ticket_counter().set(ticket);
+    }
+
+    /// Returns `ProofMapIndex` with participant keys by ticket numbers.
+    pub fn tickets(&self) -> ProofMapIndex<T, u64, PublicKey> {
+        ProofMapIndex::new(TICKETS_TABLE, self.view.clone())
+    }
+
+    /// Returns `ProofMapIndex` with participant keys by attestation hashes.
+    pub fn attestations(&self) -> ProofMapIndex<T, Hash, PublicKey> {
+        ProofMapIndex::new(ATTESTATIONS_TABLE, self.view.clone())
+    }
+
+    /// Returns `ProofMapIndex` with participant keys by external order ids.
+    pub fn order_ids(&self) -> ProofMapIndex<T, String, PublicKey> {
+        ProofMapIndex::new(ORDER_IDS_TABLE, self.view.clone())
+    }
+
+    /// Returns the current attestations of participants, the reverse of
+    /// `attestations`.
+    pub fn participant_attestations(&self) -> MapIndex<T, PublicKey, Hash> {
+        MapIndex::new(PARTICIPANT_ATTESTATIONS_TABLE, self.view.clone())
+    }
+
+    /// Returns the current order ids of participants, the reverse of
+    /// `order_ids`.
+    pub fn participant_order_ids(&self) -> MapIndex<T, PublicKey, String> {
+        MapIndex::new(PARTICIPANT_ORDER_IDS_TABLE, self.view.clone())
+    }
+
+    /// Returns key of the participant with the given ticket number.
+    pub fn ticket_key(&self, ticket: u64) -> Option<PublicKey> {
+        self.tickets().get(&ticket)
+    }
+
+    /// Returns keys of served participants in the order of purchase.
+    pub fn served(&self) -> ProofListIndex<T, PublicKey> {
+        ProofListIndex::new(SERVED_TABLE, self.view.clone())
+    }
+
+    /// Returns outcomes of all transactions referencing the participant.
+    pub fn participant_transactions(
+        &self,
+        public_key: &PublicKey,
+    ) -> ListIndex<T, TransactionOutcome> {
+        ListIndex::new_in_family(PARTICIPANT_TRANSACTIONS_TABLE, public_key, self.view.clone())
+    }
+
+    /// Returns `ProofMapIndex` with queue checkpoints by block heights.
+    pub fn checkpoints(&self) -> ProofMapIndex<T, u64, Checkpoint> {
+        ProofMapIndex::new(CHECKPOINTS_TABLE, self.view.clone())
+    }
+
+    /// Returns the checkpoint of the block at the given height.
+    pub fn checkpoint(&self, height: u64) -> Option<Checkpoint> {
+        self.checkpoints().get(&height)
+    }
+
+    /// Returns `ProofListIndex` with the queue event log.
+    pub fn events(&self) -> ProofListIndex<T, QueueEvent> {
+        ProofListIndex::new(EVENTS_TABLE, self.view.clone())
+    }
+
+    /// Returns events appended to participant histories by their hashes.
+    pub fn history_events(&self) -> MapIndex<T, Hash, QueueEvent> {
+        MapIndex::new(HISTORY_EVENTS_TABLE, self.view.clone())
+    }
+
+    /// Returns keys of the participants whose pause expires at the given
+    /// height.
+    ///
+    /// Keys are not removed on resume, the pause is checked again when the
+    /// height is reached.
+    pub fn pause_deadlines(&self, height: u64) -> ListIndex<T, PublicKey> {
+        ListIndex::new_in_family(PAUSE_DEADLINES_TABLE, &height, self.view.clone())
+    }
+
+    /// Returns states of the participant after each change.
+    pub fn participant_versions(
+        &self,
+        public_key: &PublicKey,
+    ) -> ProofListIndex<T, ParticipantVersion> {
+        ProofListIndex::new_in_family(PARTICIPANT_VERSIONS_TABLE, public_key, self.view.clone())
+    }
+
+    /// Returns `ProofMapIndex` with the root hashes of `participant_versions`.
+    pub fn versions(&self) -> ProofMapIndex<T, PublicKey, Hash> {
+        ProofMapIndex::new(VERSIONS_TABLE, self.view.clone())
+    }
+
+    /// Returns the number of participant versions recorded at or before the
+    /// given height, the index of the version after it.
+    pub fn versions_until(&self, pub_key: &PublicKey, height: u64) -> u64 {
+        self.participant_versions(pub_key)
+            .iter()
+            .take_while(|version| version.height <= height)
+            .count() as u64
+    }
+
+    /// Returns the participant as of the end of the block at the given height.
+    pub fn participant_at(&self, pub_key: &PublicKey, height: u64) -> Option<Participant> {
+        self.versions_until(pub_key, height)
+            .checked_sub(1)
+            .and_then(|index| self.participant_versions(pub_key).get(index))
+            .map(|version| version.participant)
+    }
+
+    /// Returns active participants at the end of the block at the given
+    /// height in the queue order.
+    pub fn waiting_participants_at(&self, height: u64) -> Vec<Participant> {
+        let mut waiting = self.participants()
+            .keys()
+            .filter_map(|key| self.participant_at(&key, height))
+            .filter(|x| x.is_active())
+            .collect::<Vec<_>>();
+        waiting.sort_by(|x, y| self.order_decs(y, x));
+        waiting
+    }
+
+    fn config_entry(&self) -> Entry<T, ServiceConfig> {
+        Entry::new(CONFIG_ENTRY, self.view.clone())
+    }
+
+    /// Returns the service configuration applied at genesis.
+    pub fn config(&self) -> ServiceConfig {
+        self.config_entry().get().unwrap_or_default()
+    }
+
+    /// Stores the service configuration.
+    pub fn set_config(&mut self, config: ServiceConfig) {
+        self.config_entry().set(config);
+    }
+
+    fn schema_version_entry(&self) -> Entry<T, u64> {
+        Entry::new(SCHEMA_VERSION_ENTRY, self.view.clone())
+    }
+
+    /// Returns the version of the stored data layout, `0` if the data was
+    /// written before the version was recorded.
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version_entry().get().unwrap_or_default()
+    }
+
+    /// Stores the version of the data layout.
+    pub fn set_schema_version(&mut self, version: u64) {
+        self.schema_version_entry().set(version);
+    }
+
+    fn snapshot_entry(&self) -> Entry<T, SnapshotInfo> {
+        Entry::new(SNAPSHOT_ENTRY, self.view.clone())
+    }
+
+    /// Returns the snapshot imported at genesis, if any.
+    pub fn snapshot(&self) -> Option<SnapshotInfo> {
+        self.snapshot_entry().get()
+    }
+
+    fn called_at_entry(&self) -> Entry<T, u64> {
+        Entry::new(CALLED_AT_ENTRY, &self.view)
+    }
+
+    /// Height of the block the participant at the head of the queue was
+    /// called at.
+    pub fn called_at(&self) -> u64 {
+        self.called_at_entry().get().unwrap_or(0)
+    }
+
+    fn queue_stats_entry(&self) -> Entry<T, QueueStats> {
+        Entry::new(QUEUE_STATS_ENTRY, self.view.clone())
+    }
+
+    /// Returns aggregated queue statistics.
+    pub fn queue_stats(&self) -> QueueStats {
+        self.queue_stats_entry().get().unwrap_or_default()
+    }
+
+    /// Returns the hash of the registered table.
+    pub fn table_hash(&self, table: ServiceTable) -> Hash {
+        match table {
+            ServiceTable::Participants => self.participants().object_hash(),
+            ServiceTable::Slots => self.slots().object_hash(),
+            ServiceTable::ParticipantSlots => self.participant_slots().object_hash(),
+            ServiceTable::QueueStats => self.queue_stats().object_hash(),
+            ServiceTable::Tickets => self.tickets().object_hash(),
+            ServiceTable::Attestations => self.attestations().object_hash(),
+            ServiceTable::OrderIds => self.order_ids().object_hash(),
+            ServiceTable::Checkpoints => self.checkpoints().object_hash(),
+            ServiceTable::Events => self.events().object_hash(),
+            ServiceTable::Snapshot => self
+                .snapshot()
+                .map_or_else(Hash::zero, |info| info.object_hash()),
+            ServiceTable::Served => self.served().object_hash(),
+            ServiceTable::Config => self
+                .config_entry()
+                .get()
+                .map_or_else(Hash::zero, |config| config.object_hash()),
+            ServiceTable::Versions => self.versions().object_hash(),
+        }
+    }
+
     /// Returns the state hash of service.
     pub fn state_hash(&self) -> Vec<Hash> {
-        vec![self.participants().object_hash()]
+        ServiceTable::ALL
+            .iter()
+            .map(|&table| self.table_hash(table))
+            .collect()
     }
 
     fn order_decs(&self, d1: &Participant, d2: &Participant) -> Ordering
     {
         let sort_by_timestamp = d2.timestamp.cmp(&d1.timestamp);
-        // if sort_by_timestamp != Ordering::Equal
-        // {
-        //     return sort_by_timestamp;
-        // }
-        sort_by_timestamp
+        if sort_by_timestamp != Ordering::Equal
+        {
+            return sort_by_timestamp;
+        }
+        d2.ticket.cmp(&d1.ticket)
     }
 
-    /// Returns first participant.
+    /// Returns first participant, paused participants are skipped.
     pub fn first_participant(&self) -> Option<Participant> {
         let participants = self.participants();
-        let p = participants.iter()
+        participants.iter()
             .map(|x| x.1)
-            .filter(|x| !x.have_bought && !x.removed)
+            .filter(|x| x.is_active())
             .max_by(|x, y| self.order_decs(x, y))
-            .unwrap();
-       Some(p)
     }
 
-    /// Create new participant and append first record to its history.
+    /// Returns waiting participants in the queue order, paused participants
+    /// are skipped.
+    pub fn waiting_participants(&self) -> Vec<Participant> {
+        let mut waiting = self.participants()
+            .iter()
+            .map(|x| x.1)
+            .filter(|x| x.is_active())
+            .collect::<Vec<_>>();
+        waiting.sort_by(|x, y| self.order_decs(y, x));
+        waiting
+    }
+
+    /// Create new participant with the next ticket number and append first
+    /// record to its history.
     pub fn add_participant(
         &mut self,
         key: &PublicKey,
         timestamp: u64,
         have_bought: bool,
         removed: bool,
+        deposit: u64,
         transaction: &Hash,
     ) {
+        let ticket = self.last_ticket() + 1;
         let created_participant = {
             let mut history = self.participant_history(key);
             history.push(*transaction);
             let history_hash = history.object_hash();
 
-            Participant::new(
-                key,
-                timestamp,
-                have_bought,
-                removed,
-                history.len(),
-                &history_hash,
-            )
+            Participant {
+                deposit,
+                ..Participant::new(
+                    key,
+                    timestamp,
+                    have_bought,
+                    removed,
+                    history.len(),
+                    &history_hash,
+                    ticket,
+                )
+            }
         };
+        self.set_last_ticket(ticket);
+        self.tickets().put(&ticket, *key);
+        self.record_version(&created_participant);
         self.participants().put(key, created_participant);
+        self.update_stats(QueueStats::add);
+        self.record_event(QueueEventKind::Joined, key, transaction);
+    }
+
+    /// Imports participants of the snapshot with empty histories and records
+    /// the snapshot information.
+    ///
+    /// The snapshot has to be verified by the caller.
+    pub fn import_snapshot(&mut self, snapshot: &QueueSnapshot) {
+        for imported in &snapshot.participants {
+            let history_hash = self.participant_history(&imported.key).object_hash();
+            let participant = Participant::new(
+                &imported.key,
+                imported.timestamp,
+                imported.have_bought,
+                imported.removed,
+                0,
+                &history_hash,
+                imported.ticket,
+            );
+            // the pause keeps its height on the old network, so it expires
+            // as it would have there
+            let participant = Participant {
+                paused: imported.paused,
+                paused_at: imported.paused_at,
+                ..participant
+            };
+            if participant.paused {
+                self.schedule_pause_expiry(&participant.key, participant.paused_at);
+            }
+
+            self.tickets().put(&participant.ticket, participant.key);
+            if participant.have_bought {
+                self.served().push(participant.key);
+            }
+            self.record_version(&participant);
+            self.participants().put(&participant.key, participant);
+
+            self.update_stats(|stats| {
+                let stats = stats.add();
+                if imported.have_bought {
+                    stats.buy()
+                } else if imported.removed {
+                    stats.remove()
+                } else {
+                    stats
+                }
+            });
+        }
+        self.set_last_ticket(snapshot.last_ticket());
+        self.snapshot_entry().set(snapshot.info());
     }
 
     /// Participant have bought a phone
@@ -102,14 +562,13 @@ where
         &mut self,
         participant: Participant,
         transaction: &Hash
-    ) {
-        let participant = {
-            let mut history = self.participant_history(&participant.key);
-            history.push(*transaction);
-            let history_hash = history.object_hash();
-            participant.buy(&history_hash)
-        };
-        self.participants().put(&participant.key, participant.clone());
+    ) -> Result<(), SchemaError> {
+        let key = participant.key;
+        self.served().push(key);
+        self.update_participant(participant, transaction, Participant::buy)?;
+        self.update_stats(QueueStats::buy);
+        self.record_event(QueueEventKind::Bought, &key, transaction);
+        Ok(())
     }
 
     /// Remove a participant.
@@ -117,13 +576,319 @@ where
         &mut self,
         participant: Participant,
         transaction: &Hash
-    ) {
-        let participant = {
-            let mut history = self.participant_history(&participant.key);
-            history.push(*transaction);
-            let history_hash = history.object_hash();
-            participant.remove(&history_hash)
+    ) -> Result<(), SchemaError> {
+        let key = participant.key;
+        self.update_participant(participant, transaction, Participant::remove)?;
+        self.release_slot(&key);
+        self.update_stats(QueueStats::remove);
+        self.record_event(QueueEventKind::Removed, &key, transaction);
+        Ok(())
+    }
+
+    /// Pause waiting of the participant.
+    pub fn pause_participant(
+        &mut self,
+        participant: Participant,
+        transaction: &Hash
+    ) -> Result<(), SchemaError> {
+        let key = participant.key;
+        let height = self.current_height();
+        self.update_participant(participant, transaction, |participant, history_hash| {
+            participant.pause(height, history_hash)
+        })?;
+        self.schedule_pause_expiry(&key, height);
+        self.record_event(QueueEventKind::Paused, &key, transaction);
+        Ok(())
+    }
+
+    /// Resume waiting of the participant.
+    pub fn resume_participant(
+        &mut self,
+        participant: Participant,
+        transaction: &Hash
+    ) -> Result<(), SchemaError> {
+        let key = participant.key;
+        self.update_participant(participant, transaction, Participant::resume)?;
+        self.record_event(QueueEventKind::Resumed, &key, transaction);
+        Ok(())
+    }
+
+    /// Schedules the expiration of the pause started at `paused_at`, at the
+    /// block being created if the pause is already over.
+    fn schedule_pause_expiry(&mut self, key: &PublicKey, paused_at: u64) {
+        let max_pause = self.config().max_pause;
+        if max_pause == 0 {
+            return;
+        }
+        let deadline = paused_at
+            .saturating_add(max_pause)
+            .max(self.current_height());
+        self.pause_deadlines(deadline).push(*key);
+    }
+
+    /// Removes participants paused for longer than the configured maximum
+    /// pause at the end of the block being created.
+    ///
+    /// Only the participants scheduled for this height are visited.
+    pub fn expire_pauses(&mut self) {
+        let config = self.config();
+        let height = self.current_height();
+
+        let keys = self.pause_deadlines(height).iter().collect::<Vec<_>>();
+        for key in keys {
+            // the participant may have resumed or paused again since
+            let expired = self
+                .participant(&key)
+                .filter(|x| x.paused && config.pause_expired(x.paused_at, height));
+            if let Some(participant) = expired {
+                self.expire_participant(participant);
+            }
+        }
+        self.pause_deadlines(height).clear();
+    }
+
+    /// Removes the participant at the head of the queue who didn't buy
+    /// within the configured claim window at the end of the block being
+    /// created, the deposit of the participant is forfeited.
+    pub fn expire_claim(&mut self) {
+        let config = self.config();
+        let height = self.current_height();
+
+        let head = match self.first_participant() {
+            Some(head) => head,
+            None => return,
         };
-        self.participants().put(&participant.key, participant.clone());
+        let previous_head = height
+            .checked_sub(1)
+            .and_then(|height| self.checkpoint(height))
+            .and_then(|checkpoint| checkpoint.head());
+        if previous_head == Some(head.key) && config.claim_expired(self.called_at(), height) {
+            self.expire_participant(head);
+        }
+    }
+
+    /// Removes the waiting participant on expiration, releasing the booked
+    /// slot and forfeiting the deposit.
+    fn expire_participant(&mut self, participant: Participant) {
+        let key = participant.key;
+        let deposit = participant.deposit;
+        let height = self.current_height();
+        let event = QueueEvent::new(QueueEventKind::Expired, &key, height, &Hash::zero());
+        let event_hash = event.object_hash();
+
+        let mut history = self.participant_history(&key);
+        history.push(event_hash);
+        let updated = participant.clone().expire(&history.object_hash());
+        invariants::check_expiration(&participant, &updated)
+            .expect("Invalid expiration of a waiting participant");
+        self.record_version(&updated);
+        self.participants().put(&key, updated);
+        self.release_slot(&key);
+        self.update_stats(|stats| stats.expire().forfeit(deposit));
+        self.history_events().put(&event_hash, event.clone());
+        self.events().push(event);
+    }
+
+    /// Publish a new pickup slot.
+    pub fn publish_slot(&mut self, id: &Hash, store: &str, start: u64, end: u64, capacity: u64) {
+        let slot = Slot::new(store, start, end, capacity, 0);
+        self.slots().put(id, slot);
+    }
+
+    /// Book a place in the slot for the participant.
+    ///
+    /// Fails if the slot has no free places left.
+    pub fn book_slot(
+        &mut self,
+        participant: Participant,
+        id: &Hash,
+        slot: Slot,
+        transaction: &Hash
+    ) -> Result<(), SchemaError> {
+        if slot.is_full() {
+            return Err(SchemaError::SlotIsFull);
+        }
+        self.slots().put(id, slot.book());
+        self.participant_slots().put(&participant.key, *id);
+        self.update_participant(participant, transaction, Participant::touch)
+    }
+
+    /// Returns the place booked by the participant to its slot.
+    fn release_slot(&mut self, key: &PublicKey) {
+        let id = match self.participant_slots().get(key) {
+            Some(id) => id,
+            None => return,
+        };
+        if let Some(slot) = self.slot(&id) {
+            self.slots().put(&id, slot.release());
+        }
+        self.participant_slots().remove(key);
+    }
+
+    /// Attach an attestation hash and/or an external order id to the participant.
+    ///
+    /// A new attestation or order id replaces the one attached earlier, the
+    /// previous one no longer resolves to the participant.
+    pub fn attest_participant(
+        &mut self,
+        participant: Participant,
+        attestation: Option<Hash>,
+        order_id: Option<&String>,
+        transaction: &Hash
+    ) -> Result<(), SchemaError> {
+        let key = participant.key;
+        if let Some(attestation) = attestation {
+            if let Some(previous) = self.participant_attestations().get(&key) {
+                self.attestations().remove(&previous);
+            }
+            self.attestations().put(&attestation, key);
+            self.participant_attestations().put(&key, attestation);
+        }
+        if let Some(order_id) = order_id {
+            if let Some(previous) = self.participant_order_ids().get(&key) {
+                self.order_ids().remove(&previous);
+            }
+            self.order_ids().put(order_id, key);
+            self.participant_order_ids().put(&key, order_id.clone());
+        }
+        self.update_participant(participant, transaction, Participant::touch)
+    }
+
+    /// Append the transaction to the participant history and store the
+    /// updated participant, checking the transition invariants.
+    fn update_participant<F>(
+        &mut self,
+        participant: Participant,
+        transaction: &Hash,
+        update: F,
+    ) -> Result<(), SchemaError>
+    where
+        F: FnOnce(Participant, &Hash) -> Participant,
+    {
+        let mut history = self.participant_history(&participant.key);
+        history.push(*transaction);
+        let history_hash = history.object_hash();
+
+        let updated = update(participant.clone(), &history_hash);
+        invariants::check_transition(&participant, &updated)
+            .map_err(|_| SchemaError::InvalidTransition)?;
+        self.record_version(&updated);
+        self.participants().put(&updated.key, updated);
+        Ok(())
+    }
+
+    /// Height of the block being created.
+    fn current_height(&self) -> u64 {
+        blockchain::Schema::new(self.view.clone())
+            .block_hashes_by_height()
+            .len()
+    }
+
+    /// Records the participant state for the block being created, replacing
+    /// the state recorded earlier in the same block.
+    pub(crate) fn record_version(&mut self, participant: &Participant) {
+        let height = self.current_height();
+        let mut versions = self.participant_versions(&participant.key);
+        let version = ParticipantVersion {
+            height,
+            participant: participant.clone(),
+        };
+        match versions.last() {
+            Some(ref last) if last.height == height => versions.set(versions.len() - 1, version),
+            _ => versions.push(version),
+        }
+        self.versions().put(&participant.key, versions.object_hash());
+    }
+
+    /// Appends the event to the event log.
+    fn record_event(&mut self, kind: QueueEventKind, key: &PublicKey, transaction: &Hash) {
+        let height = self.current_height();
+        self.events().push(QueueEvent::new(kind, key, height, transaction));
+    }
+
+    /// Records the queue checkpoint for the block being created and the
+    /// `Called` event if the head of the queue has changed.
+    pub fn record_checkpoint(&mut self) {
+        let height = self.current_height();
+        let head = self.first_participant().map(|participant| participant.key);
+
+        let previous_head = height
+            .checked_sub(1)
+            .and_then(|height| self.checkpoint(height))
+            .and_then(|checkpoint| checkpoint.head());
+        if let Some(ref key) = head {
+            if head != previous_head {
+                self.record_event(QueueEventKind::Called, key, &Hash::zero());
+                self.called_at_entry().set(height);
+            }
+        }
+
+        let checkpoint = Checkpoint::new(
+            height,
+            head,
+            &self.participants().object_hash(),
+            self.queue_stats(),
+        );
+        self.checkpoints().put(&height, checkpoint);
+    }
+
+    /// Records outcomes of the transactions of the block being committed
+    /// for the participants they reference, failed ones included.
+    pub fn record_transaction_outcomes(&mut self) {
+        let general_schema = blockchain::Schema::new(self.view.clone());
+        let height = self.current_height();
+
+        for (position, tx_hash) in general_schema
+            .block_transactions(Height(height))
+            .iter()
+            .enumerate()
+        {
+            let raw = match general_schema.transactions().get(&tx_hash) {
+                Some(message) => message.payload().clone(),
+                None => continue,
+            };
+            if raw.service_id() != SERVICE_ID {
+                continue;
+            }
+
+            let key = match ParticipantTransactions::tx_from_raw(raw)
+                .ok()
+                .and_then(|tx| tx.participant_key().cloned())
+            {
+                Some(key) => key,
+                None => continue,
+            };
+            let result = match general_schema.transaction_results().get(&tx_hash) {
+                Some(result) => result.0,
+                None => continue,
+            };
+
+            let mut outcome = TransactionOutcome {
+                tx_hash,
+                height,
+                position: position as u64,
+                success: result.is_ok(),
+                panic: false,
+                code: 0,
+                description: String::new(),
+            };
+            if let Err(error) = result {
+                match error.error_type() {
+                    TransactionErrorType::Panic => outcome.panic = true,
+                    TransactionErrorType::Code(code) => outcome.code = u32::from(code),
+                }
+                outcome.description = error.description().unwrap_or_default().to_owned();
+            }
+            self.participant_transactions(&key).push(outcome);
+        }
+    }
+
+    /// Updates the aggregated queue statistics.
+    pub(crate) fn update_stats<F>(&mut self, update: F)
+    where
+        F: FnOnce(QueueStats) -> QueueStats,
+    {
+        let stats = update(self.queue_stats());
+        self.queue_stats_entry().set(stats);
     }
 }
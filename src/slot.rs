@@ -0,0 +1,51 @@
+use super::proto;
+
+/// Stores information about a pickup slot
+#[derive(Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Slot", serde_pb_convert)]
+pub struct Slot {
+    /// Store the slot belongs to.
+    pub store: String,
+    /// Start of the time range.
+    pub start: u64,
+    /// End of the time range.
+    pub end: u64,
+    /// Number of participants the slot can serve.
+    pub capacity: u64,
+    /// Number of booked places.
+    pub booked: u64,
+}
+
+impl Slot {
+    /// Creates new slot
+    pub fn new(store: &str, start: u64, end: u64, capacity: u64, booked: u64) -> Self {
+        Self {
+            store: store.to_owned(),
+            start,
+            end,
+            capacity,
+            booked,
+        }
+    }
+
+    /// Checks if the slot has no free places
+    pub fn is_full(&self) -> bool {
+        self.booked >= self.capacity
+    }
+
+    /// book
+    pub fn book(self) -> Self {
+        Self {
+            booked: self.booked + 1,
+            ..self
+        }
+    }
+
+    /// Frees a booked place
+    pub fn release(self) -> Self {
+        Self {
+            booked: self.booked.saturating_sub(1),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Signed snapshot of participants imported at genesis.
+//!
+//! A snapshot seeds the queue when it is migrated to a new network. It lists
+//! participants with their tickets, timestamps and statuses and is signed by
+//! the operator of the old network, whose key must be listed in
+//! `ServiceConfig::operators` of the new one. The snapshot is passed with the
+//! genesis configuration, imported in `Service::initialize` and its hash is
+//! stored in `Schema::snapshot`, so anyone can check which snapshot the chain
+//! started from.
+use exonum::crypto::{self, Hash, PublicKey, SecretKey, Signature};
+use exonum_merkledb::ObjectHash;
+
+use std::collections::HashSet;
+
+use super::{
+    export::{ExportRecord, ExportStatus},
+    proto,
+};
+
+/// Participant from the snapshot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::SnapshotParticipant")]
+pub struct SnapshotParticipant {
+    /// Public key of the participant.
+    pub key: PublicKey,
+    /// Ticket number.
+    pub ticket: u64,
+    /// Queue timestamp.
+    pub timestamp: u64,
+    /// Participant bought a phone.
+    pub have_bought: bool,
+    /// Participant was removed from the queue.
+    pub removed: bool,
+    /// Participant paused waiting.
+    pub paused: bool,
+    /// Height of the block the participant paused at on the old network.
+    pub paused_at: u64,
+}
+
+impl<'a> From<&'a ExportRecord> for SnapshotParticipant {
+    fn from(record: &'a ExportRecord) -> Self {
+        Self {
+            key: record.key,
+            ticket: record.ticket,
+            timestamp: record.timestamp,
+            have_bought: record.status == ExportStatus::Bought,
+            removed: record.status == ExportStatus::Removed,
+            paused: record.status == ExportStatus::Paused,
+            paused_at: record.paused_at,
+        }
+    }
+}
+
+/// Signed snapshot of participants.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QueueSnapshot {
+    /// Participants ordered by tickets.
+    pub participants: Vec<SnapshotParticipant>,
+    /// Key the snapshot is signed with.
+    pub signer: PublicKey,
+    /// Signature of the snapshot hash.
+    pub signature: Signature,
+}
+
+impl QueueSnapshot {
+    /// Creates a snapshot of the participants signed with the given key.
+    pub fn sign(
+        mut participants: Vec<SnapshotParticipant>,
+        signer: &PublicKey,
+        secret_key: &SecretKey,
+    ) -> Self {
+        participants.sort_by_key(|participant| participant.ticket);
+        let signature = crypto::sign(snapshot_hash(&participants).as_ref(), secret_key);
+        Self {
+            participants,
+            signer: *signer,
+            signature,
+        }
+    }
+
+    /// Returns the hash of the snapshot participants.
+    pub fn hash(&self) -> Hash {
+        snapshot_hash(&self.participants)
+    }
+
+    /// Returns the last ticket number in the snapshot.
+    pub fn last_ticket(&self) -> u64 {
+        self.participants
+            .iter()
+            .map(|participant| participant.ticket)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Checks the signature and that participant keys and tickets are unique.
+    pub fn verify(&self) -> Result<(), failure::Error> {
+        if !crypto::verify(&self.signature, self.hash().as_ref(), &self.signer) {
+            bail!("Invalid snapshot signature");
+        }
+
+        let mut keys = HashSet::new();
+        let mut tickets = HashSet::new();
+        for participant in &self.participants {
+            if participant.ticket == 0 {
+                bail!("Zero ticket of participant {}", participant.key.to_hex());
+            }
+            if participant.have_bought && participant.removed {
+                bail!(
+                    "Participant {} is both bought and removed",
+                    participant.key.to_hex()
+                );
+            }
+            if participant.paused && (participant.have_bought || participant.removed) {
+                bail!(
+                    "Participant {} is paused but not waiting",
+                    participant.key.to_hex()
+                );
+            }
+            if !participant.paused && participant.paused_at != 0 {
+                bail!(
+                    "Participant {} has a pause height but is not paused",
+                    participant.key.to_hex()
+                );
+            }
+            if !keys.insert(participant.key) {
+                bail!("Duplicate participant {}", participant.key.to_hex());
+            }
+            if !tickets.insert(participant.ticket) {
+                bail!("Duplicate ticket {}", participant.ticket);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns information about the snapshot stored in the schema.
+    pub fn info(&self) -> SnapshotInfo {
+        SnapshotInfo {
+            hash: self.hash(),
+            signer: self.signer,
+            participants: self.participants.len() as u64,
+        }
+    }
+}
+
+/// Computes the hash of the snapshot participants.
+///
+/// The hash is taken over the concatenated hashes of participants in the
+/// snapshot order.
+pub fn snapshot_hash(participants: &[SnapshotParticipant]) -> Hash {
+    let bytes = participants
+        .iter()
+        .flat_map(|participant| participant.object_hash().as_ref().to_vec())
+        .collect::<Vec<_>>();
+    crypto::hash(&bytes)
+}
+
+/// Snapshot the queue was imported from at genesis.
+#[derive(Clone, Debug, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::SnapshotInfo", serde_pb_convert)]
+pub struct SnapshotInfo {
+    /// Hash of the snapshot participants.
+    pub hash: Hash,
+    /// Key the snapshot is signed with.
+    pub signer: PublicKey,
+    /// Number of imported participants.
+    pub participants: u64,
+}
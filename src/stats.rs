@@ -0,0 +1,66 @@
+use super::proto;
+
+/// Aggregated queue statistics maintained by transactions
+#[derive(Clone, Debug, Default, PartialEq, ProtobufConvert)]
+#[exonum(pb = "proto::QueueStats", serde_pb_convert)]
+pub struct QueueStats {
+    /// Number of participants ever added.
+    pub total_added: u64,
+    /// Number of participants waiting in the queue.
+    pub waiting: u64,
+    /// Number of participants who bought a phone.
+    pub bought: u64,
+    /// Number of removed participants.
+    pub removed: u64,
+    /// Number of participants removed on expiration.
+    pub expired: u64,
+    /// Sum of the deposits of expired participants.
+    pub forfeited: u64,
+}
+
+impl QueueStats {
+    /// participant added
+    pub fn add(self) -> Self {
+        Self {
+            total_added: self.total_added + 1,
+            waiting: self.waiting + 1,
+            ..self
+        }
+    }
+
+    /// participant bought a phone
+    pub fn buy(self) -> Self {
+        Self {
+            waiting: self.waiting - 1,
+            bought: self.bought + 1,
+            ..self
+        }
+    }
+
+    /// participant removed
+    pub fn remove(self) -> Self {
+        Self {
+            waiting: self.waiting - 1,
+            removed: self.removed + 1,
+            ..self
+        }
+    }
+
+    /// participant removed on expiration
+    pub fn expire(self) -> Self {
+        Self {
+            waiting: self.waiting - 1,
+            removed: self.removed + 1,
+            expired: self.expired + 1,
+            ..self
+        }
+    }
+
+    /// deposit of an expired participant forfeited
+    pub fn forfeit(self, deposit: u64) -> Self {
+        Self {
+            forfeited: self.forfeited + deposit,
+            ..self
+        }
+    }
+}
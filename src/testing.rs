@@ -0,0 +1,268 @@
+//! Testkit helpers for integration tests of the queue service.
+//!
+//! `QueueTestKit` wraps a single-validator `TestKit` running the service and
+//! provides typed helpers to sign and send the service transactions, check
+//! their execution results by `transactions::Error` code and fetch verified
+//! participant information. Enable the `testing` feature to use it:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! iphone_queue = { version = "0.1", features = ["testing"] }
+//! ```
+use exonum::{
+    api::node::public::explorer::{TransactionQuery, TransactionResponse},
+    crypto::{self, Hash, PublicKey, SecretKey},
+    messages::{self, RawTransaction, Signed},
+};
+use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
+use rand::{seq::SliceRandom, Rng};
+use serde_json::Value;
+
+use std::collections::BTreeSet;
+
+use crate::{
+    api::{ParticipantInfo, ParticipantQuery},
+    config::ServiceConfig,
+    transactions::{Add, Attest, BookSlot, Buy, Error, Pause, PublishSlot, Remove, Resume},
+    verify::VerifiedParticipant,
+    Service, SERVICE_NAME,
+};
+
+/// Start of the slots published by the scenario builders.
+pub const SLOT_START: u64 = 1000;
+/// End of the slots published by the scenario builders.
+pub const SLOT_END: u64 = 2000;
+
+/// Testkit running the queue service.
+#[derive(Debug)]
+pub struct QueueTestKit {
+    /// Underlying testkit.
+    pub testkit: TestKit,
+    api: TestKitApi,
+    operator: (PublicKey, SecretKey),
+}
+
+impl QueueTestKit {
+    /// Creates a testkit with the default service configuration.
+    pub fn new() -> Self {
+        Self::with_config(ServiceConfig::default())
+    }
+
+    /// Creates a testkit with the given service configuration.
+    ///
+    /// The key of the testkit operator is added to the configured operators.
+    pub fn with_config(mut config: ServiceConfig) -> Self {
+        let operator = crypto::gen_keypair();
+        config.operators.push(operator.0);
+
+        let testkit = TestKitBuilder::validator()
+            .with_service(Service::new(config))
+            .create();
+        let api = testkit.api();
+        Self {
+            testkit,
+            api,
+            operator,
+        }
+    }
+
+    /// Returns the testkit API.
+    pub fn api(&self) -> &TestKitApi {
+        &self.api
+    }
+
+    /// Returns the key used to sign `PublishSlot` transactions.
+    pub fn operator_key(&self) -> PublicKey {
+        self.operator.0
+    }
+
+    /// Returns the consensus keys of the validators.
+    pub fn validator_keys(&self) -> Vec<PublicKey> {
+        self.testkit
+            .network()
+            .validators()
+            .iter()
+            .map(|validator| validator.public_keys().consensus_key)
+            .collect()
+    }
+
+    /// Creates a block with all transactions from the pool.
+    pub fn create_block(&mut self) {
+        self.testkit.create_block();
+    }
+
+    /// Sends a signed transaction through the explorer API.
+    pub fn send(&self, tx: &Signed<RawTransaction>) -> Hash {
+        let response: TransactionResponse = self
+            .api
+            .public(ApiKind::Explorer)
+            .query(&json!({ "tx_body": messages::to_hex_string(tx) }))
+            .post("v1/transactions")
+            .unwrap();
+        assert_eq!(response.tx_hash, tx.hash());
+        response.tx_hash
+    }
+
+    /// Sends an `Add` transaction signed by a random key.
+    pub fn add(&self, key: &PublicKey, timestamp: u64) -> Hash {
+        let (pk, sk) = crypto::gen_keypair();
+        self.send(&Add::sign(&pk, key, timestamp, &sk))
+    }
+
+    /// Sends a `Buy` transaction signed by a random key.
+    pub fn buy(&self, key: &PublicKey, store: &str) -> Hash {
+        let (pk, sk) = crypto::gen_keypair();
+        self.send(&Buy::sign(&pk, key, store, &sk))
+    }
+
+    /// Sends a `Remove` transaction signed by a random key.
+    pub fn remove(&self, key: &PublicKey) -> Hash {
+        let (pk, sk) = crypto::gen_keypair();
+        self.send(&Remove::sign(&pk, key, &sk))
+    }
+
+    /// Sends a `PublishSlot` transaction signed by the operator key.
+    ///
+    /// The transaction hash is the identifier of the published slot.
+    pub fn publish_slot(&self, store: &str, start: u64, end: u64, capacity: u64) -> Hash {
+        let (pk, sk) = &self.operator;
+        self.send(&PublishSlot::sign(pk, store, start, end, capacity, sk))
+    }
+
+    /// Sends a `BookSlot` transaction signed by a random key.
+    pub fn book_slot(&self, key: &PublicKey, slot: &Hash) -> Hash {
+        let (pk, sk) = crypto::gen_keypair();
+        self.send(&BookSlot::sign(&pk, key, slot, &sk))
+    }
+
+    /// Sends an `Attest` transaction signed by the operator key.
+    pub fn attest(&self, key: &PublicKey, attestation: &Hash, order_id: &str) -> Hash {
+        let (pk, sk) = &self.operator;
+        self.send(&Attest::sign(pk, key, attestation, order_id, sk))
+    }
+
+    /// Sends a `Pause` transaction signed by the participant.
+    pub fn pause(&self, key: &PublicKey, secret_key: &SecretKey) -> Hash {
+        self.send(&Pause::sign(key, key, secret_key))
+    }
+
+    /// Sends a `Resume` transaction signed by the participant.
+    pub fn resume(&self, key: &PublicKey, secret_key: &SecretKey) -> Hash {
+        self.send(&Resume::sign(key, key, secret_key))
+    }
+
+    /// Returns the execution status of a committed transaction.
+    ///
+    /// Panics if the transaction is not committed.
+    pub fn tx_status(&self, tx_hash: &Hash) -> Value {
+        let info: Value = self
+            .api
+            .public(ApiKind::Explorer)
+            .query(&TransactionQuery::new(*tx_hash))
+            .get("v1/transactions")
+            .unwrap();
+        assert_eq!(info["type"], "committed", "Transaction is not committed");
+        info["status"].clone()
+    }
+
+    /// Asserts that the transaction is executed successfully.
+    pub fn assert_tx_success(&self, tx_hash: &Hash) {
+        assert_eq!(self.tx_status(tx_hash), json!({ "type": "success" }));
+    }
+
+    /// Asserts that the transaction is failed with the given error.
+    pub fn assert_tx_error(&self, tx_hash: &Hash, error: Error) {
+        let status = self.tx_status(tx_hash);
+        assert_eq!(status["type"], "error", "Transaction is not failed");
+        assert_eq!(status["code"], error.code(), "Unexpected error {}", status);
+    }
+
+    /// Fetches participant information without checking the proofs.
+    pub fn participant_info(&self, key: &PublicKey) -> ParticipantInfo {
+        self.api
+            .public(ApiKind::Service(SERVICE_NAME))
+            .query(&ParticipantQuery::by_key(*key))
+            .get("v1/iphone_queue/info")
+            .unwrap()
+    }
+
+    /// Fetches participant information and verifies the returned proofs
+    /// against the testkit validators.
+    pub fn verified_participant(&self, key: &PublicKey) -> VerifiedParticipant {
+        self.participant_info(key)
+            .verify_query(&self.validator_keys(), &ParticipantQuery::by_key(*key))
+            .unwrap()
+    }
+
+    /// Adds participants with the given timestamps and commits them.
+    ///
+    /// Returns participant keys in the order of `timestamps`.
+    pub fn queue_with_timestamps(&mut self, timestamps: &[u64]) -> Vec<PublicKey> {
+        let keys = timestamps
+            .iter()
+            .map(|&timestamp| {
+                let (key, _) = crypto::gen_keypair();
+                self.add(&key, timestamp);
+                key
+            })
+            .collect();
+        self.create_block();
+        keys
+    }
+
+    /// Adds `len` participants with distinct random timestamps below
+    /// `max_timestamp` in a random order and commits them.
+    ///
+    /// Returns participant keys in the queue order.
+    pub fn queue_with_random_timestamps(
+        &mut self,
+        len: usize,
+        max_timestamp: u64,
+    ) -> Vec<PublicKey> {
+        assert!(
+            len as u64 <= max_timestamp,
+            "Not enough distinct timestamps"
+        );
+
+        let mut rng = rand::thread_rng();
+        let mut timestamps = BTreeSet::new();
+        while timestamps.len() < len {
+            timestamps.insert(rng.gen_range(0, max_timestamp));
+        }
+        let mut timestamps = timestamps.into_iter().collect::<Vec<_>>();
+        timestamps.shuffle(&mut rng);
+
+        let keys = self.queue_with_timestamps(&timestamps);
+        let mut queue = timestamps.into_iter().zip(keys).collect::<Vec<_>>();
+        queue.sort_by_key(|&(timestamp, _)| timestamp);
+        queue.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Publishes a slot from `SLOT_START` to `SLOT_END` and commits it.
+    ///
+    /// Returns the slot identifier.
+    pub fn slot(&mut self, store: &str, capacity: u64) -> Hash {
+        let slot = self.publish_slot(store, SLOT_START, SLOT_END, capacity);
+        self.create_block();
+        self.assert_tx_success(&slot);
+        slot
+    }
+
+    /// Books the slot and buys at its store for the participant at the head
+    /// of the queue.
+    pub fn serve(&mut self, key: &PublicKey, slot: &Hash, store: &str) {
+        let book = self.book_slot(key, slot);
+        self.create_block();
+        self.assert_tx_success(&book);
+
+        let buy = self.buy(key, store);
+        self.create_block();
+        self.assert_tx_success(&buy);
+    }
+}
+
+impl Default for QueueTestKit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
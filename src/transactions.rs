@@ -2,14 +2,24 @@
 
 use exonum::{
     blockchain::{ExecutionError, ExecutionResult, Transaction, TransactionContext},
-    crypto::{PublicKey, SecretKey},
+    crypto::{Hash, PublicKey, SecretKey},
     messages::{Message, RawTransaction, Signed},
 };
+use exonum_merkledb::Fork;
 
-use super::{proto, schema::Schema, SERVICE_ID};
+use super::{
+    invariants, migration,
+    participant::Participant,
+    proto,
+    schema::{Schema, SchemaError},
+    SERVICE_ID,
+};
 
 /// Error codes emitted by pipes transactions during execution.
-#[derive(Debug, Fail)]
+///
+/// Codes are stable, new errors are only appended.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Fail)]
+#[serde(rename_all = "snake_case")]
 #[repr(u8)]
 pub enum Error {
     /// Participant already exists.
@@ -20,33 +30,271 @@ pub enum Error {
     
     /// Participant already removed.
     ///
-    /// Can be emitted by `Remove`.
+    /// Can be emitted by `Buy`, `Remove`, `BookSlot`, `Pause` or `Resume`.
     #[fail(display = "Participant already removed")]
     ParticipantAlreadyRemoved = 1,
 
     /// Can't find participant by key.
     ///
-    /// Can be emitted by `Remove`.
+    /// Can be emitted by `Buy`, `Remove`, `BookSlot`, `Attest`, `Pause` or
+    /// `Resume`.
     #[fail(display = "Can't find participant by key")]
     ParticipantNotFound = 2,
 
     /// Participant already bought a phone.
     ///
-    /// Can be emitted by `Buy`.
+    /// Can be emitted by `Buy`, `Remove`, `BookSlot`, `Pause` or `Resume`.
     #[fail(display = "Participant already bought a phone")]
     ParticipantAlreadyBought = 3,
 
     /// Participant is not first.
     ///
-    /// Can be emitted by `Buy`.
+    /// Can be emitted by `Buy` or `BookSlot`.
     #[fail(display = "Participant is not first")]
-    ParticipantIsNotFirst = 4
+    ParticipantIsNotFirst = 4,
+
+    /// Slot parameters are invalid.
+    ///
+    /// Can be emitted by `PublishSlot`.
+    #[fail(display = "Invalid slot parameters")]
+    InvalidSlot = 5,
+
+    /// Can't find slot by hash.
+    ///
+    /// Can be emitted by `BookSlot`.
+    #[fail(display = "Can't find slot by hash")]
+    SlotNotFound = 6,
+
+    /// Slot has no free places.
+    ///
+    /// Can be emitted by `BookSlot`.
+    #[fail(display = "Slot is full")]
+    SlotIsFull = 7,
+
+    /// Participant already booked a slot.
+    ///
+    /// Can be emitted by `BookSlot`.
+    #[fail(display = "Participant already booked a slot")]
+    SlotAlreadyBooked = 8,
+
+    /// Participant has no slot booked at the store.
+    ///
+    /// Can be emitted by `Buy`.
+    #[fail(display = "Participant has no slot booked at the store")]
+    NoSlotAtStore = 9,
+
+    /// Participant state transition violates queue invariants.
+    ///
+    /// Can be emitted by any transaction changing a participant.
+    #[fail(display = "Invalid participant state transition")]
+    InvalidTransition = 10,
+
+    /// Attestation or order id is already used by another participant.
+    ///
+    /// Can be emitted by `Attest`.
+    #[fail(display = "Identity is already used")]
+    IdentityAlreadyUsed = 11,
+
+    /// Neither attestation nor order id is set.
+    ///
+    /// Can be emitted by `Attest`.
+    #[fail(display = "Empty identity")]
+    EmptyIdentity = 12,
+
+    /// Transaction author is not an operator.
+    ///
+    /// Can be emitted by `PublishSlot` or `Attest`.
+    #[fail(display = "Author is not an operator")]
+    NotOperator = 13,
+
+    /// Store doesn't serve any configured queue.
+    ///
+    /// Can be emitted by `PublishSlot`.
+    #[fail(display = "Unknown store")]
+    UnknownStore = 14,
+
+    /// All phones are sold.
+    ///
+    /// Can be emitted by `Buy`.
+    #[fail(display = "Out of stock")]
+    OutOfStock = 15,
+
+    /// Transaction author is not the participant.
+    ///
+    /// Can be emitted by `Pause` or `Resume`.
+    #[fail(display = "Author is not the participant")]
+    NotParticipant = 16,
+
+    /// Participant already paused waiting.
+    ///
+    /// Can be emitted by `Pause`.
+    #[fail(display = "Participant already paused")]
+    ParticipantAlreadyPaused = 17,
+
+    /// Participant is not paused.
+    ///
+    /// Can be emitted by `Resume`.
+    #[fail(display = "Participant is not paused")]
+    ParticipantNotPaused = 18,
+
+    /// Deposit is below the configured size.
+    ///
+    /// Can be emitted by `Add`.
+    #[fail(display = "Insufficient deposit")]
+    InsufficientDeposit = 19
+}
+
+impl Error {
+    /// All errors in the order of their codes.
+    pub const ALL: [Error; 20] = [
+        Error::ParticipantAlreadyExists,
+        Error::ParticipantAlreadyRemoved,
+        Error::ParticipantNotFound,
+        Error::ParticipantAlreadyBought,
+        Error::ParticipantIsNotFirst,
+        Error::InvalidSlot,
+        Error::SlotNotFound,
+        Error::SlotIsFull,
+        Error::SlotAlreadyBooked,
+        Error::NoSlotAtStore,
+        Error::InvalidTransition,
+        Error::IdentityAlreadyUsed,
+        Error::EmptyIdentity,
+        Error::NotOperator,
+        Error::UnknownStore,
+        Error::OutOfStock,
+        Error::NotParticipant,
+        Error::ParticipantAlreadyPaused,
+        Error::ParticipantNotPaused,
+        Error::InsufficientDeposit,
+    ];
+
+    /// Returns the error code.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the error by its code.
+    pub fn from_code(code: u8) -> Option<Error> {
+        Error::ALL.get(code as usize).cloned()
+    }
+
+    /// Attaches the context to the error.
+    pub fn with_context(self, context: ErrorContext) -> ExecutionFailure {
+        ExecutionFailure {
+            error: self,
+            context,
+        }
+    }
+}
+
+/// Machine-readable context of an execution error.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ErrorContext {
+    /// Participant at the head of the queue, set on `ParticipantIsNotFirst`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first: Option<PublicKey>,
+    /// Requested slot, set on `SlotNotFound` and `SlotIsFull`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<Hash>,
+    /// Store of the booked slot, set on `NoSlotAtStore` if a slot is booked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub booked_store: Option<String>,
+    /// Number of phones in stock, set on `OutOfStock`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock: Option<u64>,
+    /// Configured deposit size, set on `InsufficientDeposit`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deposit: Option<u64>,
+}
+
+/// Execution error together with its context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecutionFailure {
+    /// Error.
+    pub error: Error,
+    /// Error context.
+    pub context: ErrorContext,
+}
+
+impl From<Error> for ExecutionFailure {
+    fn from(error: Error) -> ExecutionFailure {
+        error.with_context(ErrorContext::default())
+    }
+}
+
+/// Description of a failed transaction in its execution status.
+///
+/// Serialized to JSON as the description of the `ExecutionError`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ErrorDescription {
+    /// Error.
+    pub error: Error,
+    /// English error message.
+    pub message: String,
+    /// Error context.
+    #[serde(default)]
+    pub context: ErrorContext,
+}
+
+impl ErrorDescription {
+    /// Parses the description of a failed transaction.
+    pub fn parse(description: &str) -> Option<ErrorDescription> {
+        serde_json::from_str(description).ok()
+    }
+}
+
+impl From<ExecutionFailure> for ExecutionError {
+    fn from(value: ExecutionFailure) -> ExecutionError {
+        let description = ErrorDescription {
+            error: value.error,
+            message: value.error.to_string(),
+            context: value.context,
+        };
+        ExecutionError::with_description(
+            value.error.code(),
+            serde_json::to_string(&description).unwrap(),
+        )
+    }
 }
 
 impl From<Error> for ExecutionError {
     fn from(value: Error) -> ExecutionError {
-        let description = format!("{}", value);
-        ExecutionError::with_description(value as u8, description)
+        ExecutionFailure::from(value).into()
+    }
+}
+
+impl From<SchemaError> for Error {
+    fn from(value: SchemaError) -> Error {
+        match value {
+            SchemaError::SlotIsFull => Error::SlotIsFull,
+            SchemaError::InvalidTransition => Error::InvalidTransition,
+        }
+    }
+}
+
+impl From<SchemaError> for ExecutionError {
+    fn from(value: SchemaError) -> ExecutionError {
+        Error::from(value).into()
+    }
+}
+
+/// Returns the schema of the transaction, migrating the stored data first,
+/// so the first transaction after an upgrade reads the current layout.
+fn migrated_schema(fork: &Fork) -> Schema<&Fork> {
+    let mut schema = Schema::new(fork);
+    migration::migrate(&mut schema);
+    schema
+}
+
+/// Attaches the head of the queue to `ParticipantIsNotFirst`.
+fn not_first_context(error: Error, first: Option<&Participant>) -> ExecutionFailure {
+    match error {
+        Error::ParticipantIsNotFirst => error.with_context(ErrorContext {
+            first: first.map(|first| first.key),
+            ..ErrorContext::default()
+        }),
+        error => error.into(),
     }
 }
 
@@ -58,6 +306,8 @@ pub struct Add {
     pub key: PublicKey,
     /// timestamp
     pub timestamp: u64,
+    /// Deposit put down on joining.
+    pub deposit: u64,
 }
 
 /// Buy a phone.
@@ -66,6 +316,8 @@ pub struct Add {
 pub struct Buy {
     /// `PublicKey` of participant.
     pub key: PublicKey,
+    /// Store the phone is picked up at.
+    pub store: String,
 }
 
 /// Remove from queue.
@@ -76,6 +328,58 @@ pub struct Remove {
     pub key: PublicKey,
 }
 
+/// Publish a pickup slot.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::PublishSlot")]
+pub struct PublishSlot {
+    /// Store the slot belongs to.
+    pub store: String,
+    /// Start of the time range.
+    pub start: u64,
+    /// End of the time range.
+    pub end: u64,
+    /// Number of participants the slot can serve.
+    pub capacity: u64,
+}
+
+/// Book a pickup slot.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::BookSlot")]
+pub struct BookSlot {
+    /// `PublicKey` of participant.
+    pub key: PublicKey,
+    /// `Hash` of the slot.
+    pub slot: Hash,
+}
+
+/// Attach identity references to a participant, signed by an operator.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Attest")]
+pub struct Attest {
+    /// `PublicKey` of participant.
+    pub key: PublicKey,
+    /// `Hash` of the identity attestation, zero if not set.
+    pub attestation: Hash,
+    /// External order id, empty if not set.
+    pub order_id: String,
+}
+
+/// Pause waiting in the queue, signed by the participant.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Pause")]
+pub struct Pause {
+    /// `PublicKey` of participant.
+    pub key: PublicKey,
+}
+
+/// Resume waiting in the queue, signed by the participant.
+#[derive(Serialize, Deserialize, Clone, Debug, ProtobufConvert)]
+#[exonum(pb = "proto::Resume")]
+pub struct Resume {
+    /// `PublicKey` of participant.
+    pub key: PublicKey,
+}
+
 /// Transaction group.
 #[derive(Serialize, Deserialize, Clone, Debug, TransactionSet)]
 pub enum ParticipantTransactions {
@@ -84,7 +388,33 @@ pub enum ParticipantTransactions {
     /// Buy tx.
     Buy(Buy),
     /// Remove tx.
-    Remove(Remove)
+    Remove(Remove),
+    /// Publish slot tx.
+    PublishSlot(PublishSlot),
+    /// Book slot tx.
+    BookSlot(BookSlot),
+    /// Attest tx.
+    Attest(Attest),
+    /// Pause tx.
+    Pause(Pause),
+    /// Resume tx.
+    Resume(Resume)
+}
+
+impl ParticipantTransactions {
+    /// Returns the key of the participant the transaction refers to.
+    pub fn participant_key(&self) -> Option<&PublicKey> {
+        match self {
+            ParticipantTransactions::Add(tx) => Some(&tx.key),
+            ParticipantTransactions::Buy(tx) => Some(&tx.key),
+            ParticipantTransactions::Remove(tx) => Some(&tx.key),
+            ParticipantTransactions::PublishSlot(_) => None,
+            ParticipantTransactions::BookSlot(tx) => Some(&tx.key),
+            ParticipantTransactions::Attest(tx) => Some(&tx.key),
+            ParticipantTransactions::Pause(tx) => Some(&tx.key),
+            ParticipantTransactions::Resume(tx) => Some(&tx.key),
+        }
+    }
 }
 
 impl Add {
@@ -95,7 +425,23 @@ impl Add {
         timestamp: u64,
         sk: &SecretKey,
     ) -> Signed<RawTransaction> {
-        Message::sign_transaction(Self { key, timestamp }, SERVICE_ID, *pk, sk)
+        Self::sign_with_deposit(pk, &key, timestamp, 0, sk)
+    }
+
+    #[doc(hidden)]
+    pub fn sign_with_deposit(
+        pk: &PublicKey,
+        &key: &PublicKey,
+        timestamp: u64,
+        deposit: u64,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(
+            Self { key, timestamp, deposit },
+            SERVICE_ID,
+            *pk,
+            sk,
+        )
     }
 }
 
@@ -104,9 +450,11 @@ impl Buy {
     pub fn sign(
         pk: &PublicKey,
         &key: &PublicKey,
+        store: &str,
         sk: &SecretKey,
     ) -> Signed<RawTransaction> {
-        Message::sign_transaction(Self { key }, SERVICE_ID, *pk, sk)
+        let store = store.to_owned();
+        Message::sign_transaction(Self { key, store }, SERVICE_ID, *pk, sk)
     }
 }
 
@@ -121,43 +469,132 @@ impl Remove {
     }
 }
 
+impl PublishSlot {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        store: &str,
+        start: u64,
+        end: u64,
+        capacity: u64,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        let store = store.to_owned();
+        Message::sign_transaction(
+            Self { store, start, end, capacity },
+            SERVICE_ID,
+            *pk,
+            sk,
+        )
+    }
+}
+
+impl BookSlot {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        &key: &PublicKey,
+        &slot: &Hash,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(Self { key, slot }, SERVICE_ID, *pk, sk)
+    }
+}
+
+impl Attest {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        &key: &PublicKey,
+        &attestation: &Hash,
+        order_id: &str,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        let order_id = order_id.to_owned();
+        Message::sign_transaction(
+            Self { key, attestation, order_id },
+            SERVICE_ID,
+            *pk,
+            sk,
+        )
+    }
+}
+
+impl Pause {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        &key: &PublicKey,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(Self { key }, SERVICE_ID, *pk, sk)
+    }
+}
+
+impl Resume {
+    #[doc(hidden)]
+    pub fn sign(
+        pk: &PublicKey,
+        &key: &PublicKey,
+        sk: &SecretKey,
+    ) -> Signed<RawTransaction> {
+        Message::sign_transaction(Self { key }, SERVICE_ID, *pk, sk)
+    }
+}
+
 impl Transaction for Add {
     fn execute(&self, context: TransactionContext) -> ExecutionResult {
         let hash = context.tx_hash();
 
-        let mut schema = Schema::new(context.fork());
+        let mut schema = migrated_schema(context.fork());
 
         let key = &self.key;
 
-        if schema.participant(key).is_none() {
-            let timestamp = self.timestamp;
+        invariants::check_add(schema.participant(key).as_ref())?;
 
-            schema.add_participant(key, timestamp, false, false, &hash);
-
-            Ok(())
-        } else {
-            Err(Error::ParticipantAlreadyExists)?
+        let config = schema.config();
+        if !config.covers_deposit(self.deposit) {
+            Err(Error::InsufficientDeposit.with_context(ErrorContext {
+                deposit: Some(config.deposit),
+                ..ErrorContext::default()
+            }))?
         }
+
+        let timestamp = self.timestamp;
+        schema.add_participant(key, timestamp, false, false, self.deposit, &hash);
+
+        Ok(())
     }
 }
 
 impl Transaction for Buy {
     fn execute(&self, context: TransactionContext) -> ExecutionResult {
         let hash = context.tx_hash();
-        let mut schema = Schema::new(context.fork());
+        let mut schema = migrated_schema(context.fork());
         let key = &self.key;
 
         if let Some(participant) = schema.participant(key) {
-            if participant.have_bought {
-                Err(Error::ParticipantAlreadyBought)?
+            let first = schema.first_participant();
+            invariants::check_buy(&participant, first.as_ref())
+                .map_err(|e| not_first_context(e, first.as_ref()))?;
+
+            match schema.participant_slot(key) {
+                Some(ref slot) if slot.store == self.store => {}
+                slot => Err(Error::NoSlotAtStore.with_context(ErrorContext {
+                    booked_store: slot.map(|slot| slot.store),
+                    ..ErrorContext::default()
+                }))?,
             }
-            
-            let first = schema.first_participant().unwrap();
-            if !first.key.eq(&participant.key) {
-                Err(Error::ParticipantIsNotFirst)?
+
+            let config = schema.config();
+            if !config.in_stock(schema.queue_stats().bought) {
+                Err(Error::OutOfStock.with_context(ErrorContext {
+                    stock: Some(config.stock),
+                    ..ErrorContext::default()
+                }))?
             }
 
-            schema.participant_have_bought(participant, &hash);
+            schema.participant_have_bought(participant, &hash)?;
             Ok(())
         } else {
             Err(Error::ParticipantNotFound)?
@@ -168,16 +605,145 @@ impl Transaction for Buy {
 impl Transaction for Remove {
     fn execute(&self, context: TransactionContext) -> ExecutionResult {
         let hash = context.tx_hash();
-        let mut schema = Schema::new(context.fork());
+        let mut schema = migrated_schema(context.fork());
+        let key = &self.key;
+
+        if let Some(participant) = schema.participant(key) {
+            invariants::check_remove(&participant)?;
+
+            schema.remove_participant(participant, &hash)?;
+            Ok(())
+        } else {
+            Err(Error::ParticipantNotFound)?
+        }
+    }
+}
+
+impl Transaction for PublishSlot {
+    fn execute(&self, context: TransactionContext) -> ExecutionResult {
+        let hash = context.tx_hash();
+        let mut schema = migrated_schema(context.fork());
+
+        let config = schema.config();
+        if !config.is_operator(&context.author()) {
+            Err(Error::NotOperator)?
+        }
+
+        if self.store.is_empty() || self.end <= self.start || self.capacity == 0 {
+            Err(Error::InvalidSlot)?
+        }
+
+        if !config.is_known_store(&self.store) {
+            Err(Error::UnknownStore)?
+        }
+
+        schema.publish_slot(&hash, &self.store, self.start, self.end, self.capacity);
+        Ok(())
+    }
+}
+
+impl Transaction for BookSlot {
+    fn execute(&self, context: TransactionContext) -> ExecutionResult {
+        let hash = context.tx_hash();
+        let mut schema = migrated_schema(context.fork());
         let key = &self.key;
 
         if let Some(participant) = schema.participant(key) {
+            let first = schema.first_participant();
+            let already_booked = schema.participant_slots().contains(key);
+            invariants::check_book_slot(&participant, first.as_ref(), already_booked)
+                .map_err(|e| not_first_context(e, first.as_ref()))?;
 
-            if participant.removed {
-                Err(Error::ParticipantAlreadyRemoved)?
+            let slot_context = ErrorContext {
+                slot: Some(self.slot),
+                ..ErrorContext::default()
+            };
+            let slot = schema
+                .slot(&self.slot)
+                .ok_or_else(|| Error::SlotNotFound.with_context(slot_context.clone()))?;
+            schema
+                .book_slot(participant, &self.slot, slot, &hash)
+                .map_err(|e| match e {
+                    SchemaError::SlotIsFull => Error::SlotIsFull.with_context(slot_context),
+                    e => Error::from(e).into(),
+                })?;
+            Ok(())
+        } else {
+            Err(Error::ParticipantNotFound)?
+        }
+    }
+}
+
+impl Transaction for Attest {
+    fn execute(&self, context: TransactionContext) -> ExecutionResult {
+        let hash = context.tx_hash();
+        let author = context.author();
+        let mut schema = migrated_schema(context.fork());
+        let key = &self.key;
+
+        if !schema.config().is_operator(&author) {
+            Err(Error::NotOperator)?
+        }
+
+        let attestation = Some(self.attestation).filter(|h| *h != Hash::zero());
+        let order_id = Some(&self.order_id).filter(|id| !id.is_empty());
+
+        if attestation.is_none() && order_id.is_none() {
+            Err(Error::EmptyIdentity)?
+        }
+
+        if let Some(participant) = schema.participant(key) {
+            let attestation_used = attestation.map_or(false, |h| schema.attestations().contains(&h));
+            let order_id_used = order_id.map_or(false, |id| schema.order_ids().contains(id));
+            if attestation_used || order_id_used {
+                Err(Error::IdentityAlreadyUsed)?
             }
 
-            schema.remove_participant(participant, &hash);
+            schema.attest_participant(participant, attestation, order_id, &hash)?;
+            Ok(())
+        } else {
+            Err(Error::ParticipantNotFound)?
+        }
+    }
+}
+
+impl Transaction for Pause {
+    fn execute(&self, context: TransactionContext) -> ExecutionResult {
+        let hash = context.tx_hash();
+        let author = context.author();
+        let mut schema = migrated_schema(context.fork());
+        let key = &self.key;
+
+        if author != *key {
+            Err(Error::NotParticipant)?
+        }
+
+        if let Some(participant) = schema.participant(key) {
+            invariants::check_pause(&participant)?;
+
+            schema.pause_participant(participant, &hash)?;
+            Ok(())
+        } else {
+            Err(Error::ParticipantNotFound)?
+        }
+    }
+}
+
+impl Transaction for Resume {
+    fn execute(&self, context: TransactionContext) -> ExecutionResult {
+        let hash = context.tx_hash();
+        let author = context.author();
+        let mut schema = migrated_schema(context.fork());
+        let key = &self.key;
+
+        if author != *key {
+            Err(Error::NotParticipant)?
+        }
+
+        if let Some(participant) = schema.participant(key) {
+            invariants::check_resume(&participant)?;
+
+            schema.resume_participant(participant, &hash)?;
             Ok(())
         } else {
             Err(Error::ParticipantNotFound)?
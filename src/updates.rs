@@ -0,0 +1,240 @@
+//! Feed of queue updates for clients that don't want to poll the whole state.
+//!
+//! Updates are derived from the committed blocks: a participant update for
+//! each successful service transaction referencing a participant and a head
+//! update when the head recorded in the block checkpoint differs from the
+//! previous one. The cursor of the feed is the block height, so it stays
+//! valid across node restarts.
+//!
+//! Clients call `v1/iphone_queue/updates` with the height returned by the
+//! previous call, the request is answered at once with the updates after
+//! that height, possibly none. `Service::after_commit` buffers updates of
+//! the latest `MAX_BUFFERED_BLOCKS` blocks; older blocks, e.g. after a node
+//! restart, are scanned.
+use exonum::{
+    api::{self, ServiceApiBuilder, ServiceApiState},
+    blockchain::{self, TransactionSet},
+    crypto::{Hash, PublicKey},
+    helpers::Height,
+};
+use exonum_merkledb::Snapshot;
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+
+use super::{schema::Schema, transactions::ParticipantTransactions, SERVICE_ID};
+
+/// Maximum number of blocks scanned by a single request.
+pub const MAX_UPDATE_BLOCKS: u64 = 1024;
+/// Number of the latest blocks whose updates are kept in memory.
+pub const MAX_BUFFERED_BLOCKS: usize = 1024;
+
+/// Kind of a queue update.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueUpdateKind {
+    /// Another participant is at the head of the queue.
+    HeadChanged,
+    /// A transaction changed the participant.
+    ParticipantChanged,
+}
+
+/// Single queue update.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueueUpdate {
+    /// Height of the block that caused the update.
+    pub height: u64,
+    /// Kind of the update.
+    pub kind: QueueUpdateKind,
+    /// Participant the update refers to, `None` if the queue became empty.
+    pub key: Option<PublicKey>,
+    /// `Hash` of the transaction that changed the participant.
+    pub tx_hash: Option<Hash>,
+}
+
+/// Describes the query parameters for the `updates` endpoint.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct UpdatesQuery {
+    /// Return updates of the blocks above this height, of the last block
+    /// if omitted.
+    pub after: Option<u64>,
+    /// Return only updates of this kind.
+    pub kind: Option<QueueUpdateKind>,
+    /// Return participant updates only for this key.
+    pub pub_key: Option<PublicKey>,
+}
+
+impl UpdatesQuery {
+    fn matches(&self, update: &QueueUpdate) -> bool {
+        self.kind.map_or(true, |kind| update.kind == kind)
+            && match (update.kind, self.pub_key) {
+                (QueueUpdateKind::ParticipantChanged, Some(pub_key)) => update.key == Some(pub_key),
+                _ => true,
+            }
+    }
+}
+
+/// Updates of a range of blocks.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpdatesRange {
+    /// Height of the last scanned block, `after` of the next request.
+    pub height: u64,
+    /// Updates matching the query in the block order.
+    pub updates: Vec<QueueUpdate>,
+}
+
+/// Returns updates caused by the committed block at the given height.
+pub fn block_updates(snapshot: &dyn Snapshot, height: u64) -> Vec<QueueUpdate> {
+    let general_schema = blockchain::Schema::new(snapshot);
+    let schema = Schema::new(snapshot);
+    let mut updates = Vec::new();
+
+    for tx_hash in general_schema.block_transactions(Height(height)).iter() {
+        let succeeded = general_schema
+            .transaction_results()
+            .get(&tx_hash)
+            .map_or(false, |result| result.0.is_ok());
+        if !succeeded {
+            continue;
+        }
+
+        let raw = match general_schema.transactions().get(&tx_hash) {
+            Some(message) => message.payload().clone(),
+            None => continue,
+        };
+        if raw.service_id() != SERVICE_ID {
+            continue;
+        }
+
+        let key = ParticipantTransactions::tx_from_raw(raw)
+            .ok()
+            .and_then(|tx| tx.participant_key().cloned());
+        if let Some(key) = key {
+            updates.push(QueueUpdate {
+                height,
+                kind: QueueUpdateKind::ParticipantChanged,
+                key: Some(key),
+                tx_hash: Some(tx_hash),
+            });
+        }
+    }
+
+    if let Some(checkpoint) = schema.checkpoint(height) {
+        let previous_head = height
+            .checked_sub(1)
+            .and_then(|height| schema.checkpoint(height))
+            .and_then(|checkpoint| checkpoint.head());
+        if checkpoint.head() != previous_head {
+            updates.push(QueueUpdate {
+                height,
+                kind: QueueUpdateKind::HeadChanged,
+                key: checkpoint.head(),
+                tx_hash: None,
+            });
+        }
+    }
+    updates
+}
+
+/// Returns updates matching the query from at most `MAX_UPDATE_BLOCKS`
+/// committed blocks, scanning the blocks.
+pub fn updates(snapshot: &dyn Snapshot, query: &UpdatesQuery) -> UpdatesRange {
+    collect_updates(snapshot, query, |height| block_updates(snapshot, height))
+}
+
+fn collect_updates<F>(snapshot: &dyn Snapshot, query: &UpdatesQuery, block: F) -> UpdatesRange
+where
+    F: Fn(u64) -> Vec<QueueUpdate>,
+{
+    let last = blockchain::Schema::new(snapshot).height().0;
+    let from = query.after.map_or(last, |after| after.saturating_add(1));
+    if from > last {
+        return UpdatesRange {
+            height: query.after.unwrap_or(last),
+            updates: Vec::new(),
+        };
+    }
+
+    let to = last.min(from.saturating_add(MAX_UPDATE_BLOCKS - 1));
+    let updates = (from..=to)
+        .flat_map(block)
+        .filter(|update| query.matches(update))
+        .collect();
+    UpdatesRange {
+        height: to,
+        updates,
+    }
+}
+
+/// Buffer of the updates of the latest committed blocks.
+#[derive(Debug, Clone)]
+pub struct UpdateFeed {
+    capacity: usize,
+    blocks: Arc<RwLock<VecDeque<(u64, Vec<QueueUpdate>)>>>,
+}
+
+impl Default for UpdateFeed {
+    fn default() -> Self {
+        Self::with_capacity(MAX_BUFFERED_BLOCKS)
+    }
+}
+
+impl UpdateFeed {
+    /// Creates a feed buffering updates of at most `capacity` blocks.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: Arc::default(),
+        }
+    }
+
+    /// Buffers updates of the latest committed block, dropping the oldest
+    /// block if the buffer is full.
+    pub fn on_commit(&self, snapshot: &dyn Snapshot) {
+        let height = blockchain::Schema::new(snapshot).height().0;
+        let updates = block_updates(snapshot, height);
+
+        let mut blocks = self.blocks.write().unwrap();
+        if blocks.back().map_or(false, |&(last, _)| last + 1 != height) {
+            // a gap makes the lookup by offset invalid
+            blocks.clear();
+        }
+        blocks.push_back((height, updates));
+        while blocks.len() > self.capacity {
+            blocks.pop_front();
+        }
+    }
+
+    /// Number of blocks in the buffer.
+    pub fn buffered_blocks(&self) -> usize {
+        self.blocks.read().unwrap().len()
+    }
+
+    /// Returns updates matching the query without waiting, blocks missing
+    /// from the buffer are scanned.
+    pub fn updates(&self, snapshot: &dyn Snapshot, query: &UpdatesQuery) -> UpdatesRange {
+        let blocks = self.blocks.read().unwrap();
+        let first = blocks.front().map(|&(height, _)| height);
+        collect_updates(snapshot, query, |height| {
+            first
+                .and_then(|first| height.checked_sub(first))
+                .and_then(|offset| blocks.get(offset as usize))
+                .filter(|&&(buffered, _)| buffered == height)
+                .map(|(_, updates)| updates.clone())
+                .unwrap_or_else(|| block_updates(snapshot, height))
+        })
+    }
+
+    /// Wires the `updates` endpoint to public scope of the given `ServiceApiBuilder`.
+    pub fn wire(&self, builder: &mut ServiceApiBuilder) {
+        let feed = self.clone();
+        builder.public_scope().endpoint(
+            "v1/iphone_queue/updates",
+            move |state: &ServiceApiState, query: UpdatesQuery| -> api::Result<UpdatesRange> {
+                Ok(feed.updates(&state.snapshot(), &query))
+            },
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! Client-side verification of the proofs returned by the service API.
+use exonum::{
+    blockchain::{BlockProof, TransactionSet},
+    crypto::{Hash, PublicKey},
+    helpers::Height,
+};
+use exonum_merkledb::{BinaryKey, HashTag, MapProof, ObjectHash};
+
+use std::collections::HashSet;
+
+use crate::{
+    api::{
+        CheckpointProof, DisplayTicket, EventsRange, FirstParticipant, HistoryEntry,
+        ParticipantInfo, ParticipantQuery, QueueDisplay, SecondaryKeyProof, VersionsProof,
+    },
+    checkpoint::Checkpoint,
+    event::{QueueEvent, QueueEventKind},
+    participant::Participant,
+    schema::ServiceTable,
+    transactions::ParticipantTransactions,
+};
+
+/// Errors emitted while verifying proofs.
+#[derive(Debug, Fail)]
+pub enum ProofError {
+    /// Precommit is signed by a key outside of the validator set.
+    #[fail(display = "Precommit from unknown validator")]
+    UnknownValidator,
+
+    /// Precommit is for another block.
+    #[fail(display = "Precommit for another block")]
+    PrecommitMismatch,
+
+    /// Less than +2/3 of the validators signed the block.
+    #[fail(display = "Not enough precommits")]
+    NotEnoughPrecommits,
+
+    /// Proof can't be checked.
+    #[fail(display = "Malformed {} proof", _0)]
+    Malformed(&'static str),
+
+    /// Proof of the service table doesn't match the block `state_hash`.
+    #[fail(display = "Table proof doesn't match block state hash")]
+    StateHashMismatch,
+
+    /// Proof of the index doesn't match the service table entry.
+    #[fail(display = "Index proof doesn't match table proof")]
+    TableMismatch,
+
+    /// Proof contains no entry for the queried key.
+    #[fail(display = "Queried key is missing from proof")]
+    MissingEntry,
+
+    /// History doesn't match `history_hash`/`history_len` of the participant
+    /// or its entries don't match the signed transactions.
+    #[fail(display = "History doesn't match participant")]
+    HistoryMismatch,
+
+    /// Proven participant is not the one identified by the query.
+    #[fail(display = "Proof is for another participant than queried")]
+    QueryMismatch,
+}
+
+/// Secondary key the participant was looked up by.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecondaryKey {
+    /// Ticket number.
+    Ticket(u64),
+    /// Attestation hash.
+    Attestation(Hash),
+    /// External order id.
+    OrderId(String),
+}
+
+/// Participant with verified proofs.
+#[derive(Debug, Clone)]
+pub struct VerifiedParticipant {
+    /// Public key from the proof.
+    pub key: PublicKey,
+    /// Participant, `None` if the proof shows that it doesn't exist.
+    pub participant: Option<Participant>,
+    /// Height of the block the proofs are checked against.
+    pub height: Height,
+    /// Verified checkpoint of the queried height, if one was set.
+    pub checkpoint: Option<Checkpoint>,
+    /// Participant at the end of the queried height, `None` if no height was
+    /// set or the participant was not added yet.
+    pub participant_at_height: Option<Participant>,
+    /// Verified secondary key resolving to `key`, if the participant was
+    /// looked up by one.
+    pub secondary_key: Option<SecondaryKey>,
+    /// Index of the first record of the returned history window.
+    pub history_from: u64,
+    /// Verified transaction and expiration event hashes of the returned
+    /// history window, empty if the history is omitted.
+    pub history: Vec<Hash>,
+}
+
+/// Checks that the block is signed by +2/3 of the given validators.
+pub fn verify_block(
+    block_proof: &BlockProof,
+    validator_keys: &[PublicKey],
+) -> Result<(), ProofError> {
+    let block = &block_proof.block;
+    let block_hash = block.object_hash();
+
+    let mut voted = HashSet::new();
+    for precommit in &block_proof.precommits {
+        let payload = precommit.payload();
+        if *payload.block_hash() != block_hash || payload.height() != block.height() {
+            return Err(ProofError::PrecommitMismatch);
+        }
+        if !validator_keys.contains(&precommit.author()) {
+            return Err(ProofError::UnknownValidator);
+        }
+        voted.insert(precommit.author());
+    }
+
+    if voted.len() < validator_keys.len() * 2 / 3 + 1 {
+        return Err(ProofError::NotEnoughPrecommits);
+    }
+    Ok(())
+}
+
+/// Checks the proof of the service table against the block `state_hash` and
+/// returns the hash of the given table from the proof.
+pub fn table_hash(
+    block_proof: &BlockProof,
+    to_table: &MapProof<Hash, Hash>,
+    table: ServiceTable,
+) -> Result<Hash, ProofError> {
+    let to_table = to_table
+        .check()
+        .map_err(|_| ProofError::Malformed("table"))?;
+    if to_table.index_hash() != *block_proof.block.state_hash() {
+        return Err(ProofError::StateHashMismatch);
+    }
+    let key = table.key();
+    to_table
+        .entries()
+        .find(|(table_key, _)| **table_key == key)
+        .map(|(_, hash)| *hash)
+        .ok_or(ProofError::MissingEntry)
+}
+
+/// Checks the proof of the service table against the block `state_hash` and
+/// that the entry of the given table is the given index hash.
+pub fn verify_table(
+    block_proof: &BlockProof,
+    to_table: &MapProof<Hash, Hash>,
+    table: ServiceTable,
+    index_hash: Hash,
+) -> Result<(), ProofError> {
+    if table_hash(block_proof, to_table, table)? != index_hash {
+        return Err(ProofError::TableMismatch);
+    }
+    Ok(())
+}
+
+impl VerifiedParticipant {
+    /// Checks that the participant is the one identified by the query.
+    pub fn answers(&self, query: &ParticipantQuery) -> bool {
+        let secondary_key = match (query.ticket, query.attestation, &query.order_id) {
+            (Some(ticket), None, None) => Some(SecondaryKey::Ticket(ticket)),
+            (None, Some(attestation), None) => Some(SecondaryKey::Attestation(attestation)),
+            (None, None, Some(order_id)) => Some(SecondaryKey::OrderId(order_id.clone())),
+            (None, None, None) => None,
+            _ => return false,
+        };
+        match query.pub_key {
+            Some(pub_key) => secondary_key.is_none() && pub_key == self.key,
+            None => secondary_key.is_some() && secondary_key == self.secondary_key,
+        }
+    }
+}
+
+/// Checks the proof of a secondary key table against the block and returns
+/// the only entry of the proof.
+fn resolve_key<K>(
+    block_proof: &BlockProof,
+    to_table: &MapProof<Hash, Hash>,
+    to_key: &MapProof<K, PublicKey>,
+    table: ServiceTable,
+) -> Result<(K, PublicKey), ProofError>
+where
+    K: BinaryKey + ObjectHash + Clone,
+{
+    let to_key = to_key
+        .check()
+        .map_err(|_| ProofError::Malformed("secondary key"))?;
+    verify_table(block_proof, to_table, table, to_key.index_hash())?;
+
+    let mut entries = to_key.all_entries();
+    match (entries.next(), entries.next()) {
+        (Some((secondary_key, Some(key))), None) => Ok((secondary_key.clone(), *key)),
+        (Some((_, None)), None) => Err(ProofError::MissingEntry),
+        _ => Err(ProofError::Malformed("secondary key")),
+    }
+}
+
+impl SecondaryKeyProof {
+    /// Verifies the proof of the secondary key table against the block and
+    /// returns the secondary key with the public key it resolves to.
+    pub fn verify(&self, block_proof: &BlockProof) -> Result<(SecondaryKey, PublicKey), ProofError> {
+        match self {
+            SecondaryKeyProof::Ticket { to_table, to_key } => {
+                resolve_key(block_proof, to_table, to_key, ServiceTable::Tickets)
+                    .map(|(ticket, key)| (SecondaryKey::Ticket(ticket), key))
+            }
+            SecondaryKeyProof::Attestation { to_table, to_key } => {
+                resolve_key(block_proof, to_table, to_key, ServiceTable::Attestations)
+                    .map(|(attestation, key)| (SecondaryKey::Attestation(attestation), key))
+            }
+            SecondaryKeyProof::OrderId { to_table, to_key } => {
+                resolve_key(block_proof, to_table, to_key, ServiceTable::OrderIds)
+                    .map(|(order_id, key)| (SecondaryKey::OrderId(order_id), key))
+            }
+        }
+    }
+}
+
+/// Checks that the history entry is for the transaction with the given hash
+/// and that the decoded transaction matches the signed message, or that it
+/// is the expiration of the participant with the given hash.
+fn entry_matches(key: &PublicKey, hash: &Hash, entry: &HistoryEntry) -> bool {
+    match entry {
+        HistoryEntry::Transaction(entry) => {
+            if entry.message.hash() != *hash {
+                return false;
+            }
+            match ParticipantTransactions::tx_from_raw(entry.message.payload().clone()) {
+                Ok(decoded) => {
+                    serde_json::to_value(&decoded).ok()
+                        == serde_json::to_value(&entry.transaction).ok()
+                }
+                Err(_) => false,
+            }
+        }
+        HistoryEntry::Event(event) => {
+            event.kind == QueueEventKind::Expired
+                && event.key == *key
+                && event.object_hash() == *hash
+        }
+    }
+}
+
+impl CheckpointProof {
+    /// Verifies the last block precommits, the proof of the checkpoints table,
+    /// the proof of the checkpoint and the precommits of the checkpoint block.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> Result<Checkpoint, ProofError> {
+        verify_block(&self.block_proof, validator_keys)?;
+        verify_block(&self.checkpoint_block, validator_keys)?;
+
+        let to_checkpoint = self
+            .to_checkpoint
+            .check()
+            .map_err(|_| ProofError::Malformed("checkpoint"))?;
+        verify_table(
+            &self.block_proof,
+            &self.to_table,
+            ServiceTable::Checkpoints,
+            to_checkpoint.index_hash(),
+        )?;
+
+        let height = self.checkpoint_block.block.height().0;
+        to_checkpoint
+            .entries()
+            .find(|(&checkpoint_height, _)| checkpoint_height == height)
+            .map(|(_, checkpoint)| checkpoint.clone())
+            .ok_or(ProofError::MissingEntry)
+    }
+}
+
+impl VersionsProof {
+    /// Verifies the proof of the participant versions against the block and
+    /// returns the participant as of the end of the block at the given height.
+    pub fn verify(
+        &self,
+        block_proof: &BlockProof,
+        key: &PublicKey,
+        height: u64,
+    ) -> Result<Option<Participant>, ProofError> {
+        let to_versions = self
+            .to_versions
+            .check()
+            .map_err(|_| ProofError::Malformed("versions"))?;
+        verify_table(
+            block_proof,
+            &self.to_table,
+            ServiceTable::Versions,
+            to_versions.index_hash(),
+        )?;
+
+        let root = to_versions
+            .all_entries()
+            .find(|(versions_key, _)| *versions_key == key)
+            .ok_or(ProofError::MissingEntry)?
+            .1;
+        let (root, proof) = match (root, &self.proof) {
+            (None, None) if self.len == 0 => return Ok(None),
+            (Some(root), Some(proof)) => (*root, proof),
+            _ => return Err(ProofError::Malformed("versions")),
+        };
+        let entries = proof
+            .validate(root, self.len)
+            .map_err(|_| ProofError::Malformed("versions"))?;
+
+        let &(index, version) = entries.first().ok_or(ProofError::MissingEntry)?;
+        if index == 0 && version.height > height {
+            return Ok(None);
+        }
+        // the version is the last one at or before the height
+        let is_last = match entries.get(1) {
+            Some(&(next_index, next)) => next_index == index + 1 && next.height > height,
+            None => index + 1 == self.len,
+        };
+        if version.height > height || !is_last {
+            return Err(ProofError::Malformed("versions"));
+        }
+        Ok(Some(version.participant.clone()))
+    }
+}
+
+impl FirstParticipant {
+    /// Verifies the checkpoint proof and checks the participant against the
+    /// head of the checkpoint.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> Result<Checkpoint, ProofError> {
+        let checkpoint = self.checkpoint_proof.verify(validator_keys)?;
+        if checkpoint.head() != Some(self.key) {
+            return Err(ProofError::Malformed("first"));
+        }
+        Ok(checkpoint)
+    }
+}
+
+impl QueueDisplay {
+    /// Verifies the checkpoint proof, checks `now_serving` against the head
+    /// of the checkpoint and the state of each ticket at the height of the
+    /// checkpoint against its versions proof: `now_serving` and `next` are
+    /// waiting, `recently_served` have bought.
+    ///
+    /// Only the shown tickets are proven, the proofs don't show that no
+    /// waiting participant is left out of `next`.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> Result<Checkpoint, ProofError> {
+        let checkpoint = self.checkpoint_proof.verify(validator_keys)?;
+        let now_serving = self.now_serving.as_ref().map(|ticket| ticket.key);
+        if now_serving != checkpoint.head() {
+            return Err(ProofError::Malformed("display"));
+        }
+
+        let tickets = self
+            .now_serving
+            .iter()
+            .chain(&self.next)
+            .map(|ticket| (ticket, true))
+            .chain(self.recently_served.iter().map(|ticket| (ticket, false)))
+            .collect::<Vec<_>>();
+        if tickets.len() != self.versions_proofs.len() {
+            return Err(ProofError::Malformed("display"));
+        }
+        for ((ticket, waiting), proof) in tickets.into_iter().zip(&self.versions_proofs) {
+            let participant = proof
+                .verify(
+                    &self.checkpoint_proof.block_proof,
+                    &ticket.key,
+                    checkpoint.height,
+                )?
+                .ok_or(ProofError::MissingEntry)?;
+            let status_matches = if waiting {
+                participant.is_active()
+            } else {
+                participant.have_bought
+            };
+            if !status_matches || DisplayTicket::from(&participant) != *ticket {
+                return Err(ProofError::Malformed("display"));
+            }
+        }
+        Ok(checkpoint)
+    }
+}
+
+impl EventsRange {
+    /// Verifies the block precommits, the proof of the events table and the
+    /// proof of the returned events.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> Result<Vec<QueueEvent>, ProofError> {
+        verify_block(&self.block_proof, validator_keys)?;
+
+        let events_hash = table_hash(&self.block_proof, &self.to_table, ServiceTable::Events)?;
+
+        let proof = match self.proof {
+            Some(ref proof) => proof,
+            None if self.len == 0
+                && self.events.is_empty()
+                && events_hash == HashTag::empty_list_hash() =>
+            {
+                return Ok(Vec::new())
+            }
+            None => return Err(ProofError::MissingEntry),
+        };
+        let entries = proof
+            .validate(events_hash, self.len)
+            .map_err(|_| ProofError::Malformed("events"))?;
+
+        let events_match = if self.from < self.len {
+            !self.events.is_empty()
+                && entries.len() == self.events.len()
+                && entries.iter().zip(&self.events).enumerate().all(
+                    |(i, ((index, proven), event))| {
+                        *index == self.from + i as u64
+                            && proven.object_hash() == event.object_hash()
+                    },
+                )
+        } else {
+            // the proof of the last event shows that the range is empty
+            self.events.is_empty() && !entries.is_empty()
+        };
+        if !events_match {
+            return Err(ProofError::Malformed("events"));
+        }
+        Ok(self.events.clone())
+    }
+}
+
+impl ParticipantInfo {
+    /// Verifies the proofs and checks that the proven participant is the one
+    /// identified by the query.
+    pub fn verify_query(
+        &self,
+        validator_keys: &[PublicKey],
+        query: &ParticipantQuery,
+    ) -> Result<VerifiedParticipant, ProofError> {
+        let verified = self.verify(validator_keys)?;
+        if !verified.answers(query) {
+            return Err(ProofError::QueryMismatch);
+        }
+        Ok(verified)
+    }
+
+    /// Verifies the block precommits, the proof of the participants table,
+    /// the proof of the participant and its history, the proof of the
+    /// secondary key, and the checkpoint and the participant version of the
+    /// queried height.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> Result<VerifiedParticipant, ProofError> {
+        verify_block(&self.block_proof, validator_keys)?;
+
+        let to_participant = self
+            .participant_proof
+            .to_participant
+            .check()
+            .map_err(|_| ProofError::Malformed("participant"))?;
+        verify_table(
+            &self.block_proof,
+            &self.participant_proof.to_table,
+            ServiceTable::Participants,
+            to_participant.index_hash(),
+        )?;
+
+        let (&key, participant) = to_participant
+            .all_entries()
+            .next()
+            .ok_or(ProofError::MissingEntry)?;
+        let participant = participant.cloned();
+
+        let secondary_key = match self.secondary_proof {
+            Some(ref proof) => {
+                let (secondary_key, resolved) = proof.verify(&self.block_proof)?;
+                if resolved != key {
+                    return Err(ProofError::QueryMismatch);
+                }
+                Some(secondary_key)
+            }
+            None => None,
+        };
+
+        let history = match (&participant, &self.participant_history) {
+            (Some(participant), Some(history)) => {
+                let entries = history
+                    .proof
+                    .validate(participant.history_hash, participant.history_len)
+                    .map_err(|_| ProofError::HistoryMismatch)?;
+
+                let window_matches = entries.len() == history.transactions.len()
+                    && entries
+                        .iter()
+                        .enumerate()
+                        .all(|(i, (index, _))| *index == history.from + i as u64)
+                    && entries
+                        .iter()
+                        .zip(&history.transactions)
+                        .all(|((_, hash), entry)| entry_matches(&key, hash, entry));
+                if !window_matches {
+                    return Err(ProofError::HistoryMismatch);
+                }
+                entries.into_iter().map(|(_, hash)| *hash).collect()
+            }
+            (_, None) => Vec::new(),
+            (None, Some(_)) => return Err(ProofError::HistoryMismatch),
+        };
+
+        let (checkpoint, participant_at_height) = match self.at_height {
+            Some(ref at_height) => {
+                let checkpoint = at_height.checkpoint_proof.verify(validator_keys)?;
+                let proven = at_height.versions_proof.verify(
+                    &at_height.checkpoint_proof.block_proof,
+                    &key,
+                    checkpoint.height,
+                )?;
+                let returned = at_height.participant.as_ref().map(ObjectHash::object_hash);
+                if proven.as_ref().map(ObjectHash::object_hash) != returned {
+                    return Err(ProofError::Malformed("versions"));
+                }
+                (Some(checkpoint), proven)
+            }
+            None => (None, None),
+        };
+
+        Ok(VerifiedParticipant {
+            key,
+            participant,
+            height: self.block_proof.block.height(),
+            checkpoint,
+            participant_at_height,
+            secondary_key,
+            history_from: self
+                .participant_history
+                .as_ref()
+                .map_or(0, |history| history.from),
+            history,
+        })
+    }
+}
@@ -3,83 +3,1535 @@ extern crate serde_json;
 
 use exonum::{
     api::node::public::explorer::{TransactionQuery, TransactionResponse},
-    crypto::{self, Hash, PublicKey, SecretKey},
+    blockchain,
+    crypto::{self, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH},
     messages::{self, RawTransaction, Signed},
 };
+use exonum_merkledb::{BinaryValue, ObjectHash};
 use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
 
+use std::time::{Duration, Instant};
+
 // Import data types used in tests from the crate where the service is defined.
 use iphone_queue::{
-    api::{ParticipantInfo, GetFirstQuery, ParticipantQuery},
+    api::{
+        AuditAllQuery, AuditQuery, AuditReport, ConfigProof, ConfigQuery, DisplayQuery, DumpQuery,
+        ErrorInfo, ErrorsQuery, EventsQuery, EventsRange, FirstParticipant, GetFirstQuery,
+        HistoryAudit, HistoryEntry,
+        OperatorStats, OperatorStatsQuery, ParticipantInfo, ParticipantQuery, ParticipantRecord,
+        QueueDisplay, QueueStatsInfo, SearchQuery, SecondaryKeyProof, SlotOccupancy, SlotsQuery,
+        StatsQuery, TableProof, TableQuery, TicketQuery, TransactionOutcomeInfo,
+    },
+    config::{QueueDefinition, ServiceConfig},
+    event::QueueEventKind,
+    outcome::OutcomeStatus,
     participant::Participant,
-    transactions::{ Add, Buy, Remove},
+    schema::{Schema, ServiceTable},
+    stats::QueueStats,
+    transactions::{
+        Add, Attest, BookSlot, Buy, Error, ErrorDescription, ParticipantTransactions, Pause,
+        PublishSlot, Remove, Resume,
+    },
+    updates::{self, QueueUpdateKind, UpdateFeed, UpdatesQuery, UpdatesRange},
+    verify,
     Service,
 };
 
-/// add participant test
+/// add participant test
+#[test]
+fn test_add_participant() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    // Create and send a transaction via API
+    let (tx, _) = api.add_participant(&pk, 100);
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+
+    // Check that the user indeed is persisted by the service.
+    let p = api.get_participant(pk).unwrap();
+    assert_eq!(p.key, pk);
+    assert_eq!(p.timestamp, 100);
+
+    let f = api.get_first_key().unwrap();
+    assert_eq!(pk.to_hex(), f);
+}
+
+/// remove participant test
+#[test]
+fn test_remove_participant() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    // Create and send a transaction via API
+    let (tx, _) = api.add_participant(&pk, 100);
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+
+    // remove a participant
+    let (tx2, _) = api.remove_participant(&pk);
+    testkit.create_block();
+    api.assert_tx_status(tx2.hash(), &json!({ "type": "success" }));
+
+    // Check that the user is removed
+    let p = api.get_participant(pk).unwrap();
+    assert_eq!(true, p.removed);
+}
+
+/// buy test
+#[test]
+fn test_buy_transaction() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    
+    // Create and send a transaction via API
+    let (tx1, _) = api.add_participant(&pk1, 100);
+    let (tx2, _) = api.add_participant(&pk2, 101);
+    testkit.create_block();
+    api.assert_tx_status(tx1.hash(), &json!({ "type": "success" }));
+    api.assert_tx_status(tx2.hash(), &json!({ "type": "success" }));
+
+    // buy without a slot
+    let (tx3, _) = api.buy(&pk1, "store");
+    testkit.create_block();
+    api.assert_tx_error(tx3.hash(), Error::NoSlotAtStore);
+
+    // book a slot and buy
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    let (tx4, _) = api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    api.assert_tx_status(tx4.hash(), &json!({ "type": "success" }));
+
+    let (tx5, _) = api.buy(&pk1, "store");
+    testkit.create_block();
+    api.assert_tx_status(tx5.hash(), &json!({ "type": "success" }));
+
+    // Check that the user is removed
+    let p = api.get_participant(pk1).unwrap();
+    println!("{:?}", p);
+    assert_eq!(true, p.have_bought);    
+}
+
+/// buy at a store without the booked slot test
+#[test]
+fn test_buy_wrong_store() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    let (book, _) = api.book_slot(&pk, &slot.hash());
+    testkit.create_block();
+    api.assert_tx_status(book.hash(), &json!({ "type": "success" }));
+
+    let (buy, _) = api.buy(&pk, "other store");
+    testkit.create_block();
+    let description = api.assert_tx_error(buy.hash(), Error::NoSlotAtStore);
+    assert_eq!(description.context.booked_store, Some("store".to_owned()));
+
+    let participant = api.get_participant(pk).unwrap();
+    assert!(!participant.have_bought);
+    assert_eq!(participant.history_len, 2);
+}
+
+/// participant transaction outcomes test
+#[test]
+fn test_transaction_outcomes() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+
+    let (tx1, _) = api.add_participant(&pk, 100);
+    testkit.create_block();
+    let (tx2, _) = api.buy(&pk, "store");
+    testkit.create_block();
+
+    let outcomes: Vec<TransactionOutcomeInfo> = api
+        .inner
+        .private(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(pk))
+        .get("v1/iphone_queue/transactions")
+        .unwrap();
+
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].tx_hash, tx1.hash());
+    assert_eq!(outcomes[0].status, OutcomeStatus::Success);
+    assert_eq!(outcomes[1].tx_hash, tx2.hash());
+    assert_eq!(outcomes[1].height, outcomes[0].height + 1);
+    assert_eq!(outcomes[1].position, 0);
+    match outcomes[1].status {
+        OutcomeStatus::Error {
+            code,
+            error: Some(ref error),
+        } => {
+            assert_eq!(code, Error::NoSlotAtStore.code());
+            assert_eq!(error.error, Error::NoSlotAtStore);
+        }
+        ref status => panic!("Unexpected status {:?}", status),
+    }
+
+    // the failed purchase is not in the history
+    assert_eq!(api.get_participant(pk).unwrap().history_len, 1);
+}
+
+/// slot capacity test
+#[test]
+fn test_slot_capacity() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+
+    // second participant is not called yet
+    let (tx1, _) = api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+    let description = api.assert_tx_error(tx1.hash(), Error::ParticipantIsNotFirst);
+    assert_eq!(description.message, "Participant is not first");
+    assert_eq!(description.context.first, Some(pk1));
+
+    let (tx2, _) = api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    api.assert_tx_status(tx2.hash(), &json!({ "type": "success" }));
+    api.buy(&pk1, "store");
+    testkit.create_block();
+
+    // the only place is taken
+    let (tx3, _) = api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+    let description = api.assert_tx_error(tx3.hash(), Error::SlotIsFull);
+    assert_eq!(description.context.slot, Some(slot.hash()));
+
+    let slots = api.get_slots("store");
+    assert_eq!(slots.len(), 1);
+    assert_eq!(slots[0].slot.booked, 1);
+    assert_eq!(slots[0].available, 0);
+}
+
+/// removed participant releases the booked slot test
+#[test]
+fn test_remove_releases_slot() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    assert_eq!(api.get_slots("store")[0].available, 0);
+
+    let (tx, _) = api.remove_participant(&pk1);
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+
+    let slots = api.get_slots("store");
+    assert_eq!(slots[0].slot.booked, 0);
+    assert_eq!(slots[0].available, 1);
+
+    // the released place goes to the next participant
+    let (tx, _) = api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+    assert_eq!(api.get_slots("store")[0].slot.booked, 1);
+}
+
+/// error codes test
+#[test]
+fn test_error_codes() {
+    let (_testkit, api) = create_testkit();
+    let errors: Vec<ErrorInfo> = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ErrorsQuery {})
+        .get("v1/iphone_queue/errors")
+        .unwrap();
+
+    assert_eq!(errors.len(), Error::ALL.len());
+    for (code, info) in errors.iter().enumerate() {
+        assert_eq!(info.code as usize, code);
+        assert_eq!(Error::from_code(info.code), Some(info.error));
+    }
+    assert_eq!(errors[4].error, Error::ParticipantIsNotFirst);
+    assert_eq!(errors[4].message, "Participant is not first");
+}
+
+/// genesis configuration test
+#[test]
+fn test_service_config() {
+    let (operator_pk, operator_sk) = crypto::gen_keypair();
+    let config = ServiceConfig {
+        operators: vec![operator_pk],
+        queues: vec![QueueDefinition {
+            name: "iphone".to_owned(),
+            stores: vec!["store".to_owned()],
+        }],
+        stock: 1,
+        ..ServiceConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    // slot published by a key outside of the configured operators
+    let (tx1, _) = api.publish_slot("store", 2);
+    // slot at a store outside of the queues
+    let tx2 = PublishSlot::sign(&operator_pk, "other", 1000, 2000, 2, &operator_sk);
+    api.send_tx(&tx2);
+    let slot = PublishSlot::sign(&operator_pk, "store", 1000, 2000, 2, &operator_sk);
+    api.send_tx(&slot);
+    testkit.create_block();
+    api.assert_tx_error(tx1.hash(), Error::NotOperator);
+    api.assert_tx_error(tx2.hash(), Error::UnknownStore);
+    api.assert_tx_status(slot.hash(), &json!({ "type": "success" }));
+
+    api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    api.buy(&pk1, "store");
+    testkit.create_block();
+    api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+
+    // the only phone is sold
+    let (tx3, _) = api.buy(&pk2, "store");
+    testkit.create_block();
+    api.assert_tx_error(tx3.hash(), Error::OutOfStock);
+}
+
+/// service config proof test
+#[test]
+fn test_config_proof() {
+    let (operator, _) = crypto::gen_keypair();
+    let config = ServiceConfig {
+        operators: vec![operator],
+        stock: 10,
+        claim_window: 3,
+        deposit: 50,
+        max_pause: 5,
+        ..ServiceConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config.clone()))
+        .create();
+    testkit.create_block();
+
+    let proof: ConfigProof = testkit
+        .api()
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ConfigQuery {})
+        .get("v1/iphone_queue/config")
+        .unwrap();
+    assert_eq!(proof.config, config);
+
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    verify::verify_block(&proof.block_proof, &validator_keys).unwrap();
+    verify::verify_table(
+        &proof.block_proof,
+        &proof.to_table,
+        ServiceTable::Config,
+        proof.config.object_hash(),
+    )
+    .unwrap();
+
+    // a node can't report a larger stock than configured at genesis
+    let tampered = ServiceConfig {
+        stock: 11,
+        ..proof.config.clone()
+    };
+    assert!(verify::verify_table(
+        &proof.block_proof,
+        &proof.to_table,
+        ServiceTable::Config,
+        tampered.object_hash(),
+    )
+    .is_err());
+}
+
+/// history audit test
+#[test]
+fn test_audit_history() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+    api.remove_participant(&pk);
+    testkit.create_block();
+
+    let audit = api.audit(pk);
+    assert!(audit.consistent);
+    assert_eq!(audit.stored_len, 2);
+    assert_eq!(audit.stored_hash, audit.actual_hash);
+
+    let report: AuditReport = api
+        .inner
+        .private(ApiKind::Service("iphone_queue"))
+        .query(&AuditAllQuery {})
+        .get("v1/iphone_queue/audit/all")
+        .unwrap();
+    assert_eq!(report.checked, 1);
+    assert!(report.mismatches.is_empty());
+}
+
+/// history audit of histories of every length up to five test
+#[test]
+fn test_audit_history_lengths() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, sk) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+    assert!(api.audit(pk).consistent);
+
+    for _ in 0..2 {
+        api.pause(&pk, &sk);
+        testkit.create_block();
+        assert!(api.audit(pk).consistent);
+        api.resume(&pk, &sk);
+        testkit.create_block();
+        assert!(api.audit(pk).consistent);
+    }
+    assert_eq!(api.audit(pk).actual_len, 5);
+}
+
+/// tampered history audit test
+#[test]
+fn test_audit_tampered_history() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+    api.remove_participant(&pk);
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let participant = schema.participant(&pk).unwrap();
+    let entries = schema.participant_history(&pk).iter().collect::<Vec<_>>();
+    assert!(HistoryAudit::new(&participant, &entries).consistent);
+
+    let mut tampered = entries.clone();
+    tampered[1] = Hash::zero();
+    let audit = HistoryAudit::new(&participant, &tampered);
+    assert!(!audit.consistent);
+    assert_eq!(audit.actual_len, audit.stored_len);
+    assert_ne!(audit.actual_hash, audit.stored_hash);
+
+    let mut reordered = entries.clone();
+    reordered.swap(0, 1);
+    assert!(!HistoryAudit::new(&participant, &reordered).consistent);
+    assert!(!HistoryAudit::new(&participant, &entries[..1]).consistent);
+
+    let forged = Participant {
+        history_hash: Hash::zero(),
+        ..participant
+    };
+    assert!(!HistoryAudit::new(&forged, &entries).consistent);
+}
+
+/// operator dashboard test
+#[test]
+fn test_private_stats() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+    api.remove_participant(&pk2);
+    testkit.create_block();
+
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    let (buy, _) = api.buy(&pk1, "store");
+    testkit.create_block();
+    api.assert_tx_status(buy.hash(), &json!({ "type": "success" }));
+
+    let get_stats = |blocks: Option<u64>| -> OperatorStats {
+        api.inner
+            .private(ApiKind::Service("iphone_queue"))
+            .query(&OperatorStatsQuery { blocks })
+            .get("v1/iphone_queue/stats")
+            .unwrap()
+    };
+    let stats = get_stats(None);
+    assert_eq!(stats.waiting, 0);
+    assert_eq!(stats.removed, 1);
+    assert_eq!(stats.bought, 1);
+    assert_eq!(stats.blocks, 6);
+    let per_block = stats
+        .per_block
+        .iter()
+        .map(|block| (block.height, block.bought))
+        .collect::<Vec<_>>();
+    assert_eq!(per_block, vec![(1, 0), (2, 0), (3, 0), (4, 0), (5, 1)]);
+    assert!((stats.throughput - 0.2).abs() < 1e-9);
+
+    // throughput over the last blocks only
+    let stats = get_stats(Some(2));
+    assert_eq!(stats.per_block.len(), 2);
+    assert_eq!(stats.per_block[1].height, 5);
+    assert!((stats.throughput - 0.5).abs() < 1e-9);
+
+    let found: Vec<Participant> = api
+        .inner
+        .private(ApiKind::Service("iphone_queue"))
+        .query(&SearchQuery { prefix: pk1.to_hex()[..8].to_owned() })
+        .get("v1/iphone_queue/search")
+        .unwrap();
+    assert!(found.iter().any(|p| p.key == pk1));
+}
+
+/// operator dump test
+#[test]
+fn test_private_dump() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+    api.remove_participant(&pk2);
+    testkit.create_block();
+
+    let records: Vec<ParticipantRecord> = api
+        .inner
+        .private(ApiKind::Service("iphone_queue"))
+        .query(&DumpQuery {})
+        .get("v1/iphone_queue/dump")
+        .unwrap();
+    assert_eq!(records.len(), 2);
+    for record in &records {
+        let stored = api.get_participant(record.participant.key).unwrap();
+        let raw = Participant::from_bytes(hex::decode(&record.raw).unwrap().into()).unwrap();
+        assert_eq!(raw.to_bytes(), stored.to_bytes());
+        assert_eq!(record.participant.to_bytes(), stored.to_bytes());
+    }
+    let removed = records
+        .iter()
+        .find(|record| record.participant.key == pk2)
+        .unwrap();
+    assert!(removed.participant.removed);
+}
+
+/// queue statistics proof test
+#[test]
+fn test_queue_stats() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+    api.remove_participant(&pk1);
+    testkit.create_block();
+
+    let info: QueueStatsInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&StatsQuery {})
+        .get("v1/iphone_queue/stats")
+        .unwrap();
+    assert_eq!(info.stats.total_added, 2);
+    assert_eq!(info.stats.waiting, 1);
+    assert_eq!(info.stats.removed, 1);
+
+    assert_eq!(info.stats.expired, 0);
+
+    // the stats are proven against the state hash of the signed block
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    verify::verify_block(&info.block_proof, &validator_keys).unwrap();
+    verify::verify_table(
+        &info.block_proof,
+        &info.to_table,
+        ServiceTable::QueueStats,
+        info.stats.object_hash(),
+    )
+    .unwrap();
+
+    let tampered = QueueStats {
+        waiting: 2,
+        ..info.stats.clone()
+    };
+    assert!(verify::verify_table(
+        &info.block_proof,
+        &info.to_table,
+        ServiceTable::QueueStats,
+        tampered.object_hash(),
+    )
+    .is_err());
+}
+
+/// queue updates feed test
+#[test]
+fn test_queue_updates() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    let (tx1, _) = api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+
+    let range = api.get_updates(None, Some(pk1));
+    assert_eq!(range.height, testkit.height().0);
+    let updates = range.updates;
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[0].kind, QueueUpdateKind::ParticipantChanged);
+    assert_eq!(updates[0].tx_hash, Some(tx1.hash()));
+    assert_eq!(updates[1].kind, QueueUpdateKind::HeadChanged);
+    assert_eq!(updates[1].key, Some(pk1));
+
+    // only newer updates are returned
+    api.remove_participant(&pk1);
+    testkit.create_block();
+
+    let updates = api.get_updates(Some(range.height), None).updates;
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[1].kind, QueueUpdateKind::HeadChanged);
+    assert_eq!(updates[1].key, Some(pk2));
+
+    // the queue became empty
+    api.remove_participant(&pk2);
+    testkit.create_block();
+    let updates = api.get_updates(Some(range.height + 1), None).updates;
+    assert_eq!(updates[1].kind, QueueUpdateKind::HeadChanged);
+    assert_eq!(updates[1].key, None);
+}
+
+/// queue updates cursor after restart test
+#[test]
+fn test_queue_updates_restart() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    let cursor = testkit.height().0;
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    // the cursor is the block height, not a counter of the node process
+    let service = Box::new(Service::default()) as Box<dyn blockchain::Service>;
+    let mut testkit = testkit.stop().resume(vec![service]);
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let range = api.get_updates(Some(cursor), None);
+    assert_eq!(range.updates.len(), 2);
+    assert_eq!(range.updates[0].key, Some(pk));
+
+    testkit.create_block();
+    let range = api.get_updates(Some(range.height), None);
+    assert_eq!(range.height, testkit.height().0);
+    assert!(range.updates.is_empty());
+}
+
+/// queue updates request without new blocks test
+#[test]
+fn test_queue_updates_no_new_blocks() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    // the request is answered at once with the current cursor
+    let start = Instant::now();
+    let range = api.get_updates(Some(testkit.height().0), None);
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(range.updates.is_empty());
+    assert_eq!(range.height, testkit.height().0);
+
+    // a cursor ahead of the chain is returned unchanged
+    let range = api.get_updates(Some(testkit.height().0 + 10), None);
+    assert!(range.updates.is_empty());
+    assert_eq!(range.height, testkit.height().0 + 10);
+}
+
+/// queue updates buffer test
+#[test]
+fn test_update_feed_buffer() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let feed = UpdateFeed::with_capacity(2);
+    let cursor = testkit.height().0;
+    for timestamp in 0..3 {
+        let (pk, _) = crypto::gen_keypair();
+        api.add_participant(&pk, timestamp);
+        testkit.create_block();
+        feed.on_commit(&testkit.snapshot());
+    }
+    assert_eq!(feed.buffered_blocks(), 2);
+
+    // buffered blocks and the scanned older one give the same updates
+    let query = UpdatesQuery {
+        after: Some(cursor),
+        ..UpdatesQuery::default()
+    };
+    let snapshot = testkit.snapshot();
+    let range = feed.updates(&snapshot, &query);
+    assert_eq!(range, updates::updates(&snapshot, &query));
+    assert_eq!(range.height, testkit.height().0);
+    assert_eq!(
+        range
+            .updates
+            .iter()
+            .filter(|update| update.kind == QueueUpdateKind::ParticipantChanged)
+            .count(),
+        3
+    );
+}
+
+/// display feed test
+#[test]
+fn test_display_feed() {
+    let (mut testkit, api) = create_testkit();
+    let keys = (0..3).map(|_| crypto::gen_keypair().0).collect::<Vec<_>>();
+    api.add_participant(&keys[0], 100);
+    api.add_participant(&keys[1], 102);
+    api.add_participant(&keys[2], 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    api.book_slot(&keys[0], &slot.hash());
+    testkit.create_block();
+    api.buy(&keys[0], "store");
+    testkit.create_block();
+
+    // tickets are numbered from one without gaps
+    let mut tickets = keys
+        .iter()
+        .map(|&key| api.get_participant(key).unwrap().ticket)
+        .collect::<Vec<_>>();
+    tickets.sort();
+    assert_eq!(tickets, vec![1, 2, 3]);
+
+    let display: QueueDisplay = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&DisplayQuery {
+            next: None,
+            served: None,
+            height: None,
+        })
+        .get("v1/iphone_queue/display")
+        .unwrap();
+
+    let now_serving = display.now_serving.clone().unwrap();
+    assert_eq!(now_serving.key, keys[2]);
+    assert_eq!(display.next.len(), 1);
+    assert_eq!(display.next[0].key, keys[1]);
+    assert_eq!(display.recently_served.len(), 1);
+    assert_eq!(display.recently_served[0].key, keys[0]);
+    assert!(now_serving.ticket.starts_with("A-"));
+
+    // the display of the last block is proven
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    let checkpoint = display.verify(&validator_keys).unwrap();
+    assert_eq!(checkpoint.height, testkit.height().0);
+
+    // a waiting participant can't be shown as served
+    let mut tampered = display;
+    tampered.recently_served[0] = tampered.next[0].clone();
+    assert!(tampered.verify(&validator_keys).is_err());
+}
+
+/// lookup by ticket number test
+#[test]
+fn test_ticket_lookup() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    testkit.create_block();
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+
+    let p = api.get_participant(pk2).unwrap();
+    assert_eq!(p.ticket, 2);
+    assert_eq!(p.ticket_label(), "A-002");
+
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&TicketQuery { ticket: 2 })
+        .get("v1/iphone_queue/ticket")
+        .unwrap();
+    let to_participant = info.participant_proof.to_participant.check().unwrap();
+    let (&key, _) = to_participant.entries().next().unwrap();
+    assert_eq!(key, pk2);
+}
+
+/// tampered secondary key proof test
+#[test]
+fn test_secondary_proof_tampered() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    let by_ticket = |ticket| ParticipantQuery {
+        ticket: Some(ticket),
+        ..ParticipantQuery::default()
+    };
+    let get_info = |query: &ParticipantQuery| -> ParticipantInfo {
+        api.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(query)
+            .get("v1/iphone_queue/info")
+            .unwrap()
+    };
+
+    let info = get_info(&by_ticket(1));
+    let verified = info.verify_query(&validator_keys, &by_ticket(1)).unwrap();
+    assert_eq!(verified.key, pk1);
+    assert_eq!(verified.secondary_key, Some(verify::SecondaryKey::Ticket(1)));
+    assert!(info.verify_query(&validator_keys, &by_ticket(2)).is_err());
+
+    // the ticket of another participant doesn't resolve to this one
+    let mut tampered = get_info(&by_ticket(1));
+    tampered.secondary_proof = get_info(&by_ticket(2)).secondary_proof;
+    assert!(tampered.verify(&validator_keys).is_err());
+
+    // the ticket proof has to be in the proven state
+    let stale = get_info(&by_ticket(1));
+    let (pk3, _) = crypto::gen_keypair();
+    api.add_participant(&pk3, 102);
+    testkit.create_block();
+    let mut tampered = get_info(&by_ticket(1));
+    assert!(tampered.verify(&validator_keys).is_ok());
+    tampered.secondary_proof = stale.secondary_proof;
+    assert!(tampered.verify(&validator_keys).is_err());
+
+    // a participant proof without the ticket proof doesn't answer the query
+    let mut stripped = get_info(&by_ticket(1));
+    stripped.secondary_proof = None;
+    assert!(stripped.verify(&validator_keys).is_ok());
+    assert!(stripped.verify_query(&validator_keys, &by_ticket(1)).is_err());
+}
+
+/// lookup by order id test
+#[test]
+fn test_order_id_lookup() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    let tx = api.attest(&pk, &Hash::zero(), "order-1");
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+
+    let query = ParticipantQuery {
+        order_id: Some("order-1".to_owned()),
+        ..ParticipantQuery::default()
+    };
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&query)
+        .get("v1/iphone_queue/info")
+        .unwrap();
+
+    // order id resolves to the key ...
+    let to_key = match info.secondary_proof {
+        Some(SecondaryKeyProof::OrderId { to_key, .. }) => to_key.check().unwrap(),
+        other => panic!("Unexpected secondary proof: {:?}", other),
+    };
+    let (order_id, &key) = to_key.entries().next().unwrap();
+    assert_eq!(order_id, "order-1");
+    assert_eq!(key, pk);
+
+    // ... and the key resolves to the participant
+    let to_participant = info.participant_proof.to_participant.check().unwrap();
+    let (_, participant) = to_participant.entries().next().unwrap();
+    assert_eq!(participant.key, pk);
+}
+
+/// attestation by an operator test
+#[test]
+fn test_attest_operator() {
+    let (operator, operator_key) = crypto::gen_keypair();
+    let config = ServiceConfig {
+        operators: vec![operator],
+        ..ServiceConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    let (other, other_key) = crypto::gen_keypair();
+    let tx = Attest::sign(&other, &pk, &Hash::zero(), "order-1", &other_key);
+    api.send_tx(&tx);
+    testkit.create_block();
+    api.assert_tx_error(tx.hash(), Error::NotOperator);
+
+    let tx = Attest::sign(&operator, &pk, &Hash::zero(), "order-1", &operator_key);
+    api.send_tx(&tx);
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+}
+
+/// empty operator list test
 #[test]
-fn test_add_participant() {
+fn test_no_operators() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk, _) = crypto::gen_keypair();
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    // nobody may publish slots or attest participants
+    let (slot, _) = api.publish_slot("store", 1);
+    let attest = api.attest(&pk, &Hash::zero(), "order-1");
+    testkit.create_block();
+    api.assert_tx_error(slot.hash(), Error::NotOperator);
+    api.assert_tx_error(attest.hash(), Error::NotOperator);
+}
+
+/// repeated attestation test
+#[test]
+fn test_reattest_participant() {
     let (mut testkit, api) = create_testkit();
     let (pk, _) = crypto::gen_keypair();
-    // Create and send a transaction via API
-    let (tx, _) = api.add_participant(&pk, 100);
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+
+    let first = crypto::hash(b"first");
+    let second = crypto::hash(b"second");
+    let tx = api.attest(&pk, &first, "order-1");
     testkit.create_block();
     api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
 
-    // Check that the user indeed is persisted by the service.
-    let p = api.get_participant(pk).unwrap();
-    assert_eq!(p.key, pk);
-    assert_eq!(p.timestamp, 100);
+    let tx = api.attest(&pk, &second, "order-2");
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
 
-    let f = api.get_first_key().unwrap();
-    assert_eq!(pk.to_hex(), f);
+    // the previous identities no longer resolve to the participant
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    assert_eq!(schema.attestations().get(&first), None);
+    assert_eq!(schema.order_ids().get(&"order-1".to_owned()), None);
+    assert_eq!(schema.attestations().get(&second), Some(pk));
+    assert_eq!(schema.order_ids().get(&"order-2".to_owned()), Some(pk));
+
+    // and may be used by other participants
+    let (other, _) = crypto::gen_keypair();
+    api.add_participant(&other, 101);
+    testkit.create_block();
+    let tx = api.attest(&other, &first, "order-1");
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
 }
 
-/// remove participant test
+/// participant info verification test
 #[test]
-fn test_remove_participant() {
+fn test_verify_participant_info() {
     let (mut testkit, api) = create_testkit();
     let (pk, _) = crypto::gen_keypair();
-    // Create and send a transaction via API
-    let (tx, _) = api.add_participant(&pk, 100);
+    api.add_participant(&pk, 100);
+    testkit.create_block();
+    api.remove_participant(&pk);
     testkit.create_block();
-    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
 
-    // remove a participant
-    let (tx2, _) = api.remove_participant(&pk);
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(pk))
+        .get("v1/iphone_queue/info")
+        .unwrap();
+
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    let verified = info.verify(&validator_keys).unwrap();
+    assert_eq!(verified.key, pk);
+    assert!(verified.participant.unwrap().removed);
+    assert_eq!(verified.history.len(), 2);
+
+    // history entries are in the order of blocks with decoded transactions
+    let entries = info
+        .participant_history
+        .as_ref()
+        .unwrap()
+        .transactions
+        .iter()
+        .map(|entry| match entry {
+            HistoryEntry::Transaction(entry) => entry,
+            entry => panic!("Unexpected entry {:?}", entry),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(entries[1].height, entries[0].height + 1);
+    assert_eq!(entries[0].position, 0);
+    assert_eq!(entries[0].status, OutcomeStatus::Success);
+    match entries[0].transaction {
+        ParticipantTransactions::Add(ref tx) => assert_eq!(tx.timestamp, 100),
+        ref tx => panic!("Unexpected transaction {:?}", tx),
+    }
+    match entries[1].transaction {
+        ParticipantTransactions::Remove(ref tx) => assert_eq!(tx.key, pk),
+        ref tx => panic!("Unexpected transaction {:?}", tx),
+    }
+
+    // wrong validator set
+    let (other, _) = crypto::gen_keypair();
+    assert!(info.verify(&[other]).is_err());
+}
+
+/// participant history pagination test
+#[test]
+fn test_history_pagination() {
+    let (mut testkit, api) = create_testkit();
+    let (pk, _) = crypto::gen_keypair();
+    let (add, _) = api.add_participant(&pk, 100);
+    testkit.create_block();
+    let (remove, _) = api.remove_participant(&pk);
     testkit.create_block();
-    api.assert_tx_status(tx2.hash(), &json!({ "type": "success" }));
 
-    // Check that the user is removed
-    let p = api.get_participant(pk).unwrap();
-    assert_eq!(true, p.removed);
+    let get_info = |query: &ParticipantQuery| {
+        api.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(query)
+            .get::<ParticipantInfo>("v1/iphone_queue/info")
+    };
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+
+    let info = get_info(&ParticipantQuery::by_key(pk).with_history(1, 1)).unwrap();
+    let history = info.participant_history.as_ref().unwrap();
+    assert_eq!(history.from, 1);
+    assert_eq!(history.transactions.len(), 1);
+    let verified = info.verify(&validator_keys).unwrap();
+    assert_eq!(verified.history_from, 1);
+    assert_eq!(verified.history, vec![remove.hash()]);
+
+    let info = get_info(&ParticipantQuery::by_key(pk).with_history(0, 1)).unwrap();
+    assert_eq!(info.verify(&validator_keys).unwrap().history, vec![add.hash()]);
+
+    let info = get_info(&ParticipantQuery::by_key(pk).without_history()).unwrap();
+    assert!(info.participant_history.is_none());
+    assert!(info.verify(&validator_keys).unwrap().participant.is_some());
+
+    // window past the end of the history
+    assert!(get_info(&ParticipantQuery::by_key(pk).with_history(2, 1)).is_err());
 }
 
-/// buy test
+/// historical state queries test
 #[test]
-fn test_buy_transaction() {
+fn test_state_at_height() {
     let (mut testkit, api) = create_testkit();
     let (pk1, _) = crypto::gen_keypair();
     let (pk2, _) = crypto::gen_keypair();
-    
-    // Create and send a transaction via API
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+    let height = testkit.height().0;
+    let (pk3, _) = crypto::gen_keypair();
+    api.remove_participant(&pk1);
+    api.add_participant(&pk3, 102);
+    testkit.create_block();
+
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    let first_at = |height| {
+        api.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(&GetFirstQuery {
+                height: Some(height),
+            })
+            .get::<FirstParticipant>("v1/iphone_queue/get_first")
+            .unwrap()
+    };
+    let first = first_at(height);
+    assert_eq!(first.key, pk1);
+    assert_eq!(first.verify(&validator_keys).unwrap().height, height);
+    assert_eq!(first_at(height + 1).key, pk2);
+    assert_eq!(api.get_first_key().unwrap(), pk2.to_hex());
+
+    // the head is proven by the checkpoint
+    let mut tampered = first_at(height);
+    tampered.key = pk2;
+    assert!(tampered.verify(&validator_keys).is_err());
+
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(pk1).at_height(height))
+        .get("v1/iphone_queue/info")
+        .unwrap();
+    let verified = info.verify(&validator_keys).unwrap();
+    assert!(verified.participant.as_ref().unwrap().removed);
+    let checkpoint = verified.checkpoint.unwrap();
+    assert_eq!(checkpoint.height, height);
+    assert_eq!(checkpoint.head(), Some(pk1));
+    let at_height = verified.participant_at_height.unwrap();
+    assert!(!at_height.removed);
+    assert_eq!(at_height.history_len, 1);
+
+    // the participant at the height is proven by its versions
+    let mut tampered = info;
+    if let Some(ref mut at_height) = tampered.at_height {
+        at_height.participant = verified.participant.clone();
+    }
+    assert!(tampered.verify(&validator_keys).is_err());
+
+    // a participant added after the height has no state at it
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(pk3).at_height(height))
+        .get("v1/iphone_queue/info")
+        .unwrap();
+    let verified = info.verify(&validator_keys).unwrap();
+    assert!(verified.participant.is_some());
+    assert!(verified.participant_at_height.is_none());
+
+    let display: QueueDisplay = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&DisplayQuery {
+            next: None,
+            served: None,
+            height: Some(height),
+        })
+        .get("v1/iphone_queue/display")
+        .unwrap();
+    assert_eq!(display.verify(&validator_keys).unwrap().height, height);
+    assert_eq!(display.now_serving.as_ref().unwrap().key, pk1);
+    assert_eq!(display.next.len(), 1);
+    assert_eq!(display.versions_proofs.len(), 2);
+
+    // every shown ticket is proven at the height
+    let mut tampered = display;
+    tampered.next[0].key = pk3;
+    assert!(tampered.verify(&validator_keys).is_err());
+
+    // no blocks above the current height
+    assert!(api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&GetFirstQuery {
+            height: Some(height + 10),
+        })
+        .get::<FirstParticipant>("v1/iphone_queue/get_first")
+        .is_err());
+}
+
+/// queue event log test
+#[test]
+fn test_event_log() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    let (add, _) = api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+    let (remove, _) = api.remove_participant(&pk1);
+    testkit.create_block();
+
+    let get_events = |query: &EventsQuery| {
+        api.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(query)
+            .get::<EventsRange>("v1/iphone_queue/events")
+            .unwrap()
+    };
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+
+    let range = get_events(&EventsQuery::default());
+    let events = range.verify(&validator_keys).unwrap();
+    let kinds = events
+        .iter()
+        .map(|event| (event.kind, event.key))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds.len(), 5);
+    assert!(kinds[..2].contains(&(QueueEventKind::Joined, pk1)));
+    assert!(kinds[..2].contains(&(QueueEventKind::Joined, pk2)));
+    assert_eq!(kinds[2], (QueueEventKind::Called, pk1));
+    assert_eq!(kinds[3], (QueueEventKind::Removed, pk1));
+    assert_eq!(kinds[4], (QueueEventKind::Called, pk2));
+    assert!(events.iter().any(|event| event.tx_hash == add.hash()));
+    assert_eq!(events[3].tx_hash, remove.hash());
+    assert_eq!(events[4].height, events[3].height);
+
+    // streaming from the last seen event
+    let range = get_events(&EventsQuery {
+        from: Some(3),
+        limit: Some(1),
+    });
+    assert_eq!(range.len, 5);
+    let events = range.verify(&validator_keys).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, QueueEventKind::Removed);
+
+    let range = get_events(&EventsQuery {
+        from: Some(5),
+        limit: None,
+    });
+    assert!(range.verify(&validator_keys).unwrap().is_empty());
+    assert!(range.proof.is_some());
+
+    // events can't be hidden by claiming a shorter log or an empty range
+    let mut range = get_events(&EventsQuery {
+        from: Some(3),
+        limit: None,
+    });
+    range.events.clear();
+    assert!(range.verify(&validator_keys).is_err());
+    range.proof = None;
+    assert!(range.verify(&validator_keys).is_err());
+
+    let mut range = get_events(&EventsQuery {
+        from: Some(5),
+        limit: None,
+    });
+    range.len = 3;
+    assert!(range.verify(&validator_keys).is_err());
+}
+
+/// participant pause test
+#[test]
+fn test_pause_participant() {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, sk1) = crypto::gen_keypair();
+    let (pk2, sk2) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    testkit.create_block();
+
+    // only the participant may pause
+    let (pk, sk) = crypto::gen_keypair();
+    let foreign = Pause::sign(&pk, &pk1, &sk);
+    api.send_tx(&foreign);
+    let not_paused = api.resume(&pk2, &sk2);
+    testkit.create_block();
+    api.assert_tx_error(foreign.hash(), Error::NotParticipant);
+    api.assert_tx_error(not_paused.hash(), Error::ParticipantNotPaused);
+
+    // the paused head is skipped
+    let pause = api.pause(&pk1, &sk1);
+    testkit.create_block();
+    api.assert_tx_status(pause.hash(), &json!({ "type": "success" }));
+    assert!(api.get_participant(pk1).unwrap().paused);
+    assert_eq!(api.get_first_key().unwrap(), pk2.to_hex());
+
+    let pause = api.pause(&pk1, &sk1);
+    let (buy, _) = api.buy(&pk1, "store");
+    testkit.create_block();
+    api.assert_tx_error(pause.hash(), Error::ParticipantAlreadyPaused);
+    api.assert_tx_error(buy.hash(), Error::ParticipantIsNotFirst);
+
+    // the next participant is served while the head is paused
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    let (book, _) = api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+    api.assert_tx_status(book.hash(), &json!({ "type": "success" }));
+    let (buy, _) = api.buy(&pk2, "store");
+    testkit.create_block();
+    api.assert_tx_status(buy.hash(), &json!({ "type": "success" }));
+    assert!(api.get_participant(pk2).unwrap().have_bought);
+    assert!(api.get_participant(pk1).unwrap().paused);
+
+    // the place is kept after resuming
+    let resume = api.resume(&pk1, &sk1);
+    testkit.create_block();
+    api.assert_tx_status(resume.hash(), &json!({ "type": "success" }));
+    let participant = api.get_participant(pk1).unwrap();
+    assert!(!participant.paused);
+    assert_eq!(participant.history_len, 3);
+    assert_eq!(api.get_first_key().unwrap(), pk1.to_hex());
+}
+
+/// pause expiration test
+#[test]
+fn test_pause_expiration() {
+    let config = ServiceConfig {
+        max_pause: 2,
+        ..operator_config()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk1, sk1) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    assert_eq!(api.get_slots("store")[0].available, 0);
+
+    api.pause(&pk1, &sk1);
+    testkit.create_block();
+    // the pause started at height 3 is still active at height 4
+    testkit.create_block();
+    assert!(api.get_participant(pk1).unwrap().paused);
+    assert_eq!(
+        Schema::new(&testkit.snapshot()).pause_deadlines(5).len(),
+        1
+    );
+
+    // removed at the end of the block `max_pause` blocks after the pause
+    testkit.create_block();
+    let participant = api.get_participant(pk1).unwrap();
+    assert!(participant.removed && !participant.paused);
+    assert_eq!(participant.history_len, 4);
+    assert_eq!(api.get_slots("store")[0].available, 1);
+    assert!(Schema::new(&testkit.snapshot()).pause_deadlines(5).is_empty());
+
+    // the expiration is the last verified entry of the history
+    let info: ParticipantInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(pk1))
+        .get("v1/iphone_queue/info")
+        .unwrap();
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect::<Vec<_>>();
+    assert_eq!(info.verify(&validator_keys).unwrap().history.len(), 4);
+    match info.participant_history.as_ref().unwrap().transactions[3] {
+        HistoryEntry::Event(ref event) => {
+            assert_eq!(event.kind, QueueEventKind::Expired);
+            assert_eq!(event.key, pk1);
+            assert_eq!(event.height, 5);
+        }
+        ref entry => panic!("Unexpected entry {:?}", entry),
+    }
+
+    let info: QueueStatsInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&StatsQuery {})
+        .get("v1/iphone_queue/stats")
+        .unwrap();
+    assert_eq!(info.stats.waiting, 1);
+    assert_eq!(info.stats.removed, 1);
+    assert_eq!(info.stats.expired, 1);
+
+    let range: EventsRange = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&EventsQuery::default())
+        .get("v1/iphone_queue/events")
+        .unwrap();
+    let expired = range
+        .events
+        .iter()
+        .find(|event| event.kind == QueueEventKind::Expired)
+        .unwrap();
+    assert_eq!(expired.key, pk1);
+    assert_eq!(expired.height, 5);
+
+    let resume = api.resume(&pk1, &sk1);
+    testkit.create_block();
+    api.assert_tx_error(resume.hash(), Error::ParticipantAlreadyRemoved);
+}
+
+/// claim window expiration test
+#[test]
+fn test_claim_window() {
+    let config = ServiceConfig {
+        claim_window: 2,
+        ..operator_config()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    let (pubkey, key) = crypto::gen_keypair();
+    api.send_tx(&Add::sign_with_deposit(&pubkey, &pk1, 100, 10, &key));
+    api.add_participant(&pk2, 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    // the first participant is called at height 1
+    testkit.create_block();
+    api.book_slot(&pk1, &slot.hash());
+    testkit.create_block();
+    assert_eq!(api.get_first_key().unwrap(), pk1.to_hex());
+
+    // removed at the end of the block `claim_window` blocks after the call
+    testkit.create_block();
+    let participant = api.get_participant(pk1).unwrap();
+    assert!(participant.removed);
+    assert_eq!(participant.history_len, 3);
+    assert_eq!(api.get_first_key().unwrap(), pk2.to_hex());
+    assert_eq!(api.get_slots("store")[0].available, 1);
+
+    let info: QueueStatsInfo = api
+        .inner
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&StatsQuery {})
+        .get("v1/iphone_queue/stats")
+        .unwrap();
+    assert_eq!(info.stats.expired, 1);
+    assert_eq!(info.stats.forfeited, 10);
+
+    // the next participant buys within its window
+    api.book_slot(&pk2, &slot.hash());
+    testkit.create_block();
+    let (buy, _) = api.buy(&pk2, "store");
+    testkit.create_block();
+    api.assert_tx_status(buy.hash(), &json!({ "type": "success" }));
+    assert!(api.get_participant(pk2).unwrap().have_bought);
+}
+
+/// deposit size test
+#[test]
+fn test_deposit() {
+    let config = ServiceConfig {
+        deposit: 50,
+        ..ServiceConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let api = ParticipantsApi {
+        inner: testkit.api(),
+    };
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+
     let (tx1, _) = api.add_participant(&pk1, 100);
-    let (tx2, _) = api.add_participant(&pk2, 101);
+    let (pubkey, key) = crypto::gen_keypair();
+    let tx2 = Add::sign_with_deposit(&pubkey, &pk2, 101, 50, &key);
+    api.send_tx(&tx2);
     testkit.create_block();
-    api.assert_tx_status(tx1.hash(), &json!({ "type": "success" }));
+
+    let description = api.assert_tx_error(tx1.hash(), Error::InsufficientDeposit);
+    assert_eq!(description.context.deposit, Some(50));
+    assert!(api.get_participant(pk1).is_none());
     api.assert_tx_status(tx2.hash(), &json!({ "type": "success" }));
+    assert_eq!(api.get_participant(pk2).unwrap().deposit, 50);
+}
 
-    // buy
-    let (tx3, _) = api.buy(&pk1);
+/// proofs of all service tables test
+#[test]
+fn test_table_proofs() {
+    let (mut testkit, api) = create_populated_testkit();
+    let view = testkit.snapshot();
+    let first = Schema::new(&view).first_participant().unwrap();
+    api.buy(&first.key, "store");
     testkit.create_block();
-    api.assert_tx_status(tx3.hash(), &json!({ "type": "success" }));
 
-    // Check that the user is removed
-    let p = api.get_participant(pk1).unwrap();
-    println!("{:?}", p);
-    assert_eq!(true, p.have_bought);    
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    assert_eq!(schema.served().len(), 1);
+    assert_eq!(schema.snapshot(), None);
+
+    let state_hash = schema.state_hash();
+    assert_eq!(state_hash.len(), ServiceTable::ALL.len());
+    for &table in ServiceTable::ALL.iter() {
+        let expected = schema.table_hash(table);
+        assert_eq!(state_hash[table.index()], expected, "{:?}", table);
+        api.assert_table_proof(table, expected);
+    }
+    assert_eq!(schema.table_hash(ServiceTable::Snapshot), Hash::zero());
+    assert_eq!(
+        schema.table_hash(ServiceTable::Served),
+        schema.served().object_hash()
+    );
 }
 
+/// table proof of another table test
+#[test]
+fn test_table_proof_wrong_table() {
+    let (testkit, api) = create_populated_testkit();
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    let attestations = schema.attestations().object_hash();
+    assert_ne!(attestations, schema.order_ids().object_hash());
+
+    let proof = api.get_table_proof(ServiceTable::OrderIds);
+    assert!(verify::verify_table(
+        &proof.block_proof,
+        &proof.to_table,
+        ServiceTable::OrderIds,
+        attestations,
+    )
+    .is_err());
+
+    // the proof of the attestations table doesn't prove the order ids table
+    let proof = api.get_table_proof(ServiceTable::Attestations);
+    let order_ids = schema.order_ids().object_hash();
+    assert!(verify::verify_table(
+        &proof.block_proof,
+        &proof.to_table,
+        ServiceTable::OrderIds,
+        order_ids,
+    )
+    .is_err());
+}
 
 /// Wrapper for the cryptocurrency service API allowing to easily use it
 /// (compared to `TestKitApi` calls).
@@ -102,14 +1554,7 @@ impl ParticipantsApi {
         // Create a pre-signed transaction
         let tx = Add::sign(&pubkey, pk, timestamp, &key);
 
-        let data = messages::to_hex_string(&tx);
-        let tx_info: TransactionResponse = self
-            .inner
-            .public(ApiKind::Explorer)
-            .query(&json!({ "tx_body": data }))
-            .post("v1/transactions")
-            .unwrap();
-        assert_eq!(tx_info.tx_hash, tx.hash());
+        self.send_tx(&tx);
         (tx, key)
     }
 
@@ -122,27 +1567,77 @@ impl ParticipantsApi {
         // Create a pre-signed transaction
         let tx = Remove::sign(&pubkey, pk, &key);
 
-        let data = messages::to_hex_string(&tx);
-        let tx_info: TransactionResponse = self
-            .inner
-            .public(ApiKind::Explorer)
-            .query(&json!({ "tx_body": data }))
-            .post("v1/transactions")
-            .unwrap();
-        assert_eq!(tx_info.tx_hash, tx.hash());
+        self.send_tx(&tx);
         (tx, key)
     }
 
+    /// Generates a pause transaction signed by the participant.
+    fn pause(&self, pk: &PublicKey, sk: &SecretKey) -> Signed<RawTransaction> {
+        let tx = Pause::sign(pk, pk, sk);
+        self.send_tx(&tx);
+        tx
+    }
+
+    /// Generates a resume transaction signed by the participant.
+    fn resume(&self, pk: &PublicKey, sk: &SecretKey) -> Signed<RawTransaction> {
+        let tx = Resume::sign(pk, pk, sk);
+        self.send_tx(&tx);
+        tx
+    }
+
     /// Generates a buy transaction.
     fn buy(
         &self,
-        pk: &PublicKey
+        pk: &PublicKey,
+        store: &str
+    ) -> (Signed<RawTransaction>, SecretKey) {
+        let (pubkey, key) = crypto::gen_keypair();
+        // Create a pre-signed transaction
+        let tx = Buy::sign(&pubkey, pk, store, &key);
+
+        self.send_tx(&tx);
+        (tx, key)
+    }
+
+    /// Generates a slot publishing transaction.
+    fn publish_slot(
+        &self,
+        store: &str,
+        capacity: u64
+    ) -> (Signed<RawTransaction>, SecretKey) {
+        let (pubkey, key) = operator();
+        // Create a pre-signed transaction
+        let tx = PublishSlot::sign(&pubkey, store, 1000, 2000, capacity, &key);
+
+        self.send_tx(&tx);
+        (tx, key)
+    }
+
+    /// Generates an attestation transaction signed by the test operator.
+    fn attest(&self, pk: &PublicKey, attestation: &Hash, order_id: &str) -> Signed<RawTransaction> {
+        let (pubkey, key) = operator();
+        let tx = Attest::sign(&pubkey, pk, attestation, order_id, &key);
+        self.send_tx(&tx);
+        tx
+    }
+
+    /// Generates a slot booking transaction.
+    fn book_slot(
+        &self,
+        pk: &PublicKey,
+        slot: &Hash
     ) -> (Signed<RawTransaction>, SecretKey) {
         let (pubkey, key) = crypto::gen_keypair();
         // Create a pre-signed transaction
-        let tx = Buy::sign(&pubkey, pk, &key);
+        let tx = BookSlot::sign(&pubkey, pk, slot, &key);
+
+        self.send_tx(&tx);
+        (tx, key)
+    }
 
-        let data = messages::to_hex_string(&tx);
+    /// Sends a transaction over HTTP and checks the returned hash.
+    fn send_tx(&self, tx: &Signed<RawTransaction>) {
+        let data = messages::to_hex_string(tx);
         let tx_info: TransactionResponse = self
             .inner
             .public(ApiKind::Explorer)
@@ -150,14 +1645,41 @@ impl ParticipantsApi {
             .post("v1/transactions")
             .unwrap();
         assert_eq!(tx_info.tx_hash, tx.hash());
-        (tx, key)
+    }
+
+    fn get_table_proof(&self, table: ServiceTable) -> TableProof {
+        let proof: TableProof = self
+            .inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(&TableQuery { table })
+            .get("v1/iphone_queue/table")
+            .unwrap();
+        assert_eq!(proof.table, table);
+        proof
+    }
+
+    /// Asserts that the proof of the table returned by the service is valid
+    /// and proves the given table hash.
+    fn assert_table_proof(&self, table: ServiceTable, expected: Hash) {
+        let proof = self.get_table_proof(table);
+        assert_eq!(proof.table_hash, expected);
+        verify::verify_table(&proof.block_proof, &proof.to_table, table, expected)
+            .unwrap_or_else(|e| panic!("Invalid proof for {:?}: {}", table, e));
+    }
+
+    fn get_slots(&self, store: &str) -> Vec<SlotOccupancy> {
+        self.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(&SlotsQuery { store: Some(store.to_owned()) })
+            .get::<Vec<SlotOccupancy>>("v1/iphone_queue/slots")
+            .unwrap()
     }
 
     fn get_participant(&self, pub_key: PublicKey) -> Option<Participant> {
         let participant_info = self
             .inner
             .public(ApiKind::Service("iphone_queue"))
-            .query(&ParticipantQuery { pub_key })
+            .query(&ParticipantQuery::by_key(pub_key))
             .get::<ParticipantInfo>("v1/iphone_queue/info")
             .unwrap();
 
@@ -173,16 +1695,55 @@ impl ParticipantsApi {
         participant.cloned()
     }
 
+    fn audit(&self, pub_key: PublicKey) -> HistoryAudit {
+        self.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(&AuditQuery { pub_key })
+            .get("v1/iphone_queue/audit")
+            .unwrap()
+    }
+
+    fn get_updates(&self, after: Option<u64>, pub_key: Option<PublicKey>) -> UpdatesRange {
+        let query = UpdatesQuery {
+            after,
+            pub_key,
+            ..UpdatesQuery::default()
+        };
+        self.inner
+            .public(ApiKind::Service("iphone_queue"))
+            .query(&query)
+            .get("v1/iphone_queue/updates")
+            .unwrap()
+    }
+
     fn get_first_key(&self) -> Option<String> {
-        let first_key = self
+        let first = self
             .inner
             .public(ApiKind::Service("iphone_queue"))
-            .query(&GetFirstQuery {})
-            .get::<String>("v1/iphone_queue/get_first")
+            .query(&GetFirstQuery::default())
+            .get::<FirstParticipant>("v1/iphone_queue/get_first")
             .unwrap();
             
-        println!("{:?}", first_key);
-        Some(first_key)
+        println!("{:?}", first.key);
+        Some(first.key.to_hex())
+    }
+
+    /// Asserts that the transaction is failed with the given error and
+    /// returns the error description.
+    fn assert_tx_error(&self, tx_hash: Hash, error: Error) -> ErrorDescription {
+        let info: serde_json::Value = self
+            .inner
+            .public(ApiKind::Explorer)
+            .query(&TransactionQuery::new(tx_hash))
+            .get("v1/transactions")
+            .unwrap();
+
+        let status = &info["status"];
+        assert_eq!(status["type"], "error");
+        assert_eq!(status["code"], error.code());
+        let description = ErrorDescription::parse(status["description"].as_str().unwrap()).unwrap();
+        assert_eq!(description.error, error);
+        description
     }
 
     /// Asserts that the transaction with the given hash has a specified status.
@@ -205,9 +1766,41 @@ impl ParticipantsApi {
 
 /// Creates a testkit together with the API wrapper defined above.
 fn create_testkit() -> (TestKit, ParticipantsApi) {
-    let testkit = TestKitBuilder::validator().with_service(Service).create();
+    let testkit = TestKitBuilder::validator()
+        .with_service(Service::new(operator_config()))
+        .create();
     let api = ParticipantsApi {
         inner: testkit.api(),
     };
     (testkit, api)
 }
+
+/// Returns the key pair of the operator of the test configurations.
+fn operator() -> (PublicKey, SecretKey) {
+    crypto::gen_keypair_from_seed(&Seed::new([1; SEED_LENGTH]))
+}
+
+/// Returns the default configuration with the test operator.
+fn operator_config() -> ServiceConfig {
+    ServiceConfig {
+        operators: vec![operator().0],
+        ..ServiceConfig::default()
+    }
+}
+
+/// Creates a testkit with every service table filled in.
+fn create_populated_testkit() -> (TestKit, ParticipantsApi) {
+    let (mut testkit, api) = create_testkit();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    api.add_participant(&pk1, 100);
+    api.add_participant(&pk2, 101);
+    let (slot, _) = api.publish_slot("store", 1);
+    testkit.create_block();
+    api.book_slot(&pk1, &slot.hash());
+    let attestation = crypto::hash(b"attestation");
+    let tx = api.attest(&pk2, &attestation, "order-1");
+    testkit.create_block();
+    api.assert_tx_status(tx.hash(), &json!({ "type": "success" }));
+    (testkit, api)
+}
@@ -0,0 +1,195 @@
+//! Tests of the operator tool arguments, run with `cargo test --features cli`.
+#![cfg(feature = "cli")]
+
+use clap::ErrorKind;
+use exonum::crypto;
+
+use std::{env, fs};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use iphone_queue::cli::{self, KeyFile};
+
+/// verify without validator keys test
+#[test]
+fn test_verify_requires_validator() {
+    let (key, _) = crypto::gen_keypair();
+    let err = cli::app()
+        .get_matches_from_safe(vec!["iphone-queue-cli", "verify", key.to_hex().as_str()])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+
+    let (validator, _) = crypto::gen_keypair();
+    let matches = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "verify",
+            key.to_hex().as_str(),
+            "--validator",
+            validator.to_hex().as_str(),
+        ])
+        .unwrap();
+    let matches = matches.subcommand_matches("verify").unwrap();
+    assert_eq!(cli::validator_keys(matches).unwrap(), vec![validator]);
+}
+
+/// required validator keys test
+#[test]
+fn test_validator_keys_required() {
+    let commands: &[&[&str]] = &[
+        &["add", "--keys", "keys.json"],
+        &["book-slot", "--keys", "keys.json", "--slot", "00"],
+        &["buy", "--keys", "keys.json", "--store", "store"],
+        &["remove", "--keys", "keys.json"],
+        &["pause", "--keys", "keys.json"],
+        &["resume", "--keys", "keys.json"],
+        &["first"],
+        &["list"],
+    ];
+    let (validator, _) = crypto::gen_keypair();
+    let validator_hex = validator.to_hex();
+    for command in commands {
+        let mut args = vec!["iphone-queue-cli"];
+        args.extend_from_slice(command);
+        let err = cli::app().get_matches_from_safe(args.clone()).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingRequiredArgument, "{:?}", command);
+
+        args.extend_from_slice(&["--validator", validator_hex.as_str()]);
+        let matches = cli::app().get_matches_from_safe(args).unwrap();
+        let matches = matches.subcommand_matches(command[0]).unwrap();
+        assert_eq!(cli::validator_keys(matches).unwrap(), vec![validator]);
+    }
+}
+
+/// publish-slot arguments test
+#[test]
+fn test_publish_slot_args() {
+    let matches = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "publish-slot",
+            "--keys",
+            "operator.json",
+            "--store",
+            "store",
+            "--start",
+            "1000",
+            "--end",
+            "2000",
+            "--capacity",
+            "5",
+        ])
+        .unwrap();
+    let matches = matches.subcommand_matches("publish-slot").unwrap();
+    assert_eq!(matches.value_of("store"), Some("store"));
+    assert_eq!(cli::number(matches, "start").unwrap(), 1000);
+    assert_eq!(cli::number(matches, "end").unwrap(), 2000);
+    assert_eq!(cli::number(matches, "capacity").unwrap(), 5);
+
+    let err = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "publish-slot",
+            "--keys",
+            "operator.json",
+            "--store",
+            "store",
+            "--start",
+            "1000",
+            "--end",
+            "2000",
+        ])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+}
+
+/// invalid publish-slot number test
+#[test]
+fn test_publish_slot_invalid_number() {
+    let matches = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "publish-slot",
+            "--keys",
+            "operator.json",
+            "--store",
+            "store",
+            "--start",
+            "soon",
+            "--end",
+            "2000",
+            "--capacity",
+            "5",
+        ])
+        .unwrap();
+    let matches = matches.subcommand_matches("publish-slot").unwrap();
+    assert!(cli::number(matches, "start").is_err());
+}
+
+/// book-slot arguments test
+#[test]
+fn test_book_slot_args() {
+    let slot = crypto::hash(b"slot");
+    let (key, _) = crypto::gen_keypair();
+    let (validator, _) = crypto::gen_keypair();
+    let matches = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "book-slot",
+            "--keys",
+            "keys.json",
+            "--participant",
+            key.to_hex().as_str(),
+            "--slot",
+            slot.to_hex().as_str(),
+            "--validator",
+            validator.to_hex().as_str(),
+        ])
+        .unwrap();
+    let matches = matches.subcommand_matches("book-slot").unwrap();
+    assert_eq!(cli::slot_id(matches).unwrap(), slot);
+    assert_eq!(matches.value_of("participant"), Some(key.to_hex().as_str()));
+
+    let err = cli::app()
+        .get_matches_from_safe(vec!["iphone-queue-cli", "book-slot", "--keys", "keys.json"])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+
+    let matches = cli::app()
+        .get_matches_from_safe(vec![
+            "iphone-queue-cli",
+            "book-slot",
+            "--keys",
+            "keys.json",
+            "--slot",
+            "not a hash",
+            "--validator",
+            validator.to_hex().as_str(),
+        ])
+        .unwrap();
+    let matches = matches.subcommand_matches("book-slot").unwrap();
+    assert!(cli::slot_id(matches).is_err());
+}
+
+/// key file permissions test
+#[test]
+fn test_key_file_save() {
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let path = env::temp_dir().join(format!("iphone-queue-keys-{}.json", public_key.to_hex()));
+    let path = path.to_str().unwrap();
+    let keys = KeyFile {
+        public_key,
+        secret_key,
+    };
+    keys.save(path).unwrap();
+
+    #[cfg(unix)]
+    assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+    let loaded = KeyFile::load(path).unwrap();
+    assert_eq!(loaded.public_key, keys.public_key);
+    assert_eq!(loaded.secret_key.as_ref(), keys.secret_key.as_ref());
+
+    // existing key files are never overwritten
+    assert!(keys.save(path).is_err());
+    fs::remove_file(path).unwrap();
+}
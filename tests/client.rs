@@ -0,0 +1,170 @@
+//! Tests of the HTTP client against the testkit API, run with
+//! `cargo test --features client`.
+#![cfg(feature = "client")]
+
+use exonum::crypto::{self, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH};
+use exonum_testkit::{TestKit, TestKitApi, TestKitBuilder};
+
+use std::time::Duration;
+
+use iphone_queue::{
+    client::{ClientError, QueueClient, TxStatus},
+    config::ServiceConfig,
+    transactions::Error,
+    Service,
+};
+
+/// Time to wait for a transaction already committed by the testkit.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+fn operator() -> (PublicKey, SecretKey) {
+    crypto::gen_keypair_from_seed(&Seed::new([1; SEED_LENGTH]))
+}
+
+/// Creates a testkit with the test operator and the client of its API
+/// trusting the testkit validators, the API server runs while the returned
+/// `TestKitApi` is alive.
+fn create_client() -> (TestKit, TestKitApi, QueueClient) {
+    let config = ServiceConfig {
+        operators: vec![operator().0],
+        ..ServiceConfig::default()
+    };
+    let testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config))
+        .create();
+    let validator_keys = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| validator.public_keys().consensus_key)
+        .collect();
+    let api = testkit.api();
+    let client = QueueClient::new(&api.public_url(""), validator_keys);
+    (testkit, api, client)
+}
+
+/// queue senders and getters test
+#[test]
+fn test_client_queue() {
+    let (mut testkit, _api, client) = create_client();
+    let (pk1, sk1) = crypto::gen_keypair();
+    let (pk2, sk2) = crypto::gen_keypair();
+    let add = client.add(&pk1, 100, 0, (&pk1, &sk1)).unwrap();
+    client.add(&pk2, 200, 0, (&pk2, &sk2)).unwrap();
+    assert_eq!(client.tx_status(&add).unwrap(), TxStatus::InPool);
+    testkit.create_block();
+    assert_eq!(client.tx_status(&add).unwrap(), TxStatus::Success);
+    client.wait_for_commit(&add, TIMEOUT).unwrap();
+
+    assert_eq!(client.first().unwrap(), pk1);
+    let participant = client.participant(&pk1).unwrap().unwrap();
+    assert_eq!(participant.ticket, 1);
+    assert_eq!(participant.timestamp, 100);
+
+    let (operator_pk, operator_sk) = operator();
+    let slot = client
+        .publish_slot("store", 1000, 2000, 1, (&operator_pk, &operator_sk))
+        .unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&slot, TIMEOUT).unwrap();
+
+    let book = client.book_slot(&pk1, &slot, (&pk1, &sk1)).unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&book, TIMEOUT).unwrap();
+    let buy = client.buy(&pk1, "store", (&pk1, &sk1)).unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&buy, TIMEOUT).unwrap();
+    assert!(client.participant(&pk1).unwrap().unwrap().have_bought);
+
+    // the display is verified by the client
+    let display = client.display(5).unwrap();
+    assert_eq!(display.now_serving.map(|ticket| ticket.key), Some(pk2));
+    assert!(display.next.is_empty());
+    assert_eq!(
+        display
+            .recently_served
+            .iter()
+            .map(|ticket| ticket.key)
+            .collect::<Vec<_>>(),
+        vec![pk1]
+    );
+    assert_eq!(client.first().unwrap(), pk2);
+}
+
+/// participant senders test
+#[test]
+fn test_client_participant() {
+    let (mut testkit, _api, client) = create_client();
+    let (pk, sk) = crypto::gen_keypair();
+    client.add(&pk, 100, 0, (&pk, &sk)).unwrap();
+    testkit.create_block();
+
+    let pause = client.pause((&pk, &sk)).unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&pause, TIMEOUT).unwrap();
+    assert!(client.participant(&pk).unwrap().unwrap().paused);
+
+    let resume = client.resume((&pk, &sk)).unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&resume, TIMEOUT).unwrap();
+    assert!(!client.participant(&pk).unwrap().unwrap().paused);
+
+    let (operator_pk, operator_sk) = operator();
+    let attest = client
+        .attest(&pk, &crypto::hash(b"attestation"), "order-1", (&operator_pk, &operator_sk))
+        .unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&attest, TIMEOUT).unwrap();
+
+    let remove = client.remove(&pk, (&pk, &sk)).unwrap();
+    testkit.create_block();
+    client.wait_for_commit(&remove, TIMEOUT).unwrap();
+    let participant = client.participant(&pk).unwrap().unwrap();
+    assert!(participant.removed);
+    assert_eq!(participant.history_len, 5);
+}
+
+/// client errors test
+#[test]
+fn test_client_errors() {
+    let (mut testkit, api, client) = create_client();
+    assert_eq!(client.tx_status(&Hash::zero()).unwrap(), TxStatus::Unknown);
+
+    // failed transactions are reported with their codes
+    let (pk, sk) = crypto::gen_keypair();
+    let remove = client.remove(&pk, (&pk, &sk)).unwrap();
+    testkit.create_block();
+    match client.tx_status(&remove).unwrap() {
+        TxStatus::Error { code, .. } => assert_eq!(code, Error::ParticipantNotFound as u8),
+        status => panic!("Unexpected status {:?}", status),
+    }
+    match client.wait_for_commit(&remove, TIMEOUT) {
+        Err(ClientError::TransactionFailed { code, .. }) => {
+            assert_eq!(code, Error::ParticipantNotFound as u8)
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+
+    // absence of the participant is proven
+    assert!(client.participant(&pk).unwrap().is_none());
+
+    // pending transactions time out
+    let add = client.add(&pk, 100, 0, (&pk, &sk)).unwrap();
+    match client.wait_for_commit(&add, Duration::from_millis(0)) {
+        Err(ClientError::Timeout) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    testkit.create_block();
+
+    // responses are checked against the trusted validator set only
+    let (other, _) = crypto::gen_keypair();
+    let untrusted = QueueClient::new(&api.public_url(""), vec![other]);
+    match untrusted.participant(&pk) {
+        Err(ClientError::InvalidProof(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+    match untrusted.first() {
+        Err(ClientError::InvalidProof(_)) => {}
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,143 @@
+//! Tests of the service configuration passed through `generate-template` and
+//! `finalize`.
+
+#[macro_use]
+extern crate serde_json;
+
+use exonum::{
+    crypto::{self, PublicKey, SecretKey},
+    helpers::{
+        self,
+        fabric::{keys, CommandExtension, Context},
+    },
+};
+
+use std::{env, fs, path::PathBuf, process};
+
+use iphone_queue::{
+    config::{self, Finalize, GenerateCommonConfig, GenesisConfig, QueueDefinition, ServiceConfig},
+    snapshot::{QueueSnapshot, SnapshotParticipant},
+};
+
+/// Writes the file to the temporary directory and returns its path.
+fn write_temp(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("iphone-queue-{}-{}", process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn config_toml(operator: &PublicKey) -> String {
+    format!(
+        r#"
+operators = ["{}"]
+stock = 10
+claim_window = 3
+deposit = 50
+max_pause = 5
+
+[[queues]]
+name = "iphone"
+stores = ["store"]
+"#,
+        operator.to_hex()
+    )
+}
+
+fn signed_snapshot(pk: &PublicKey, sk: &SecretKey) -> QueueSnapshot {
+    let (key, _) = crypto::gen_keypair();
+    let participant = SnapshotParticipant {
+        key,
+        ticket: 1,
+        timestamp: 10,
+        have_bought: false,
+        removed: false,
+        paused: false,
+        paused_at: 0,
+    };
+    QueueSnapshot::sign(vec![participant], pk, sk)
+}
+
+/// Runs `generate-template` and `finalize` with the given arguments and reads
+/// the genesis configuration back as `ServiceFactory::make_service` does.
+fn round_trip(args: &[(&str, &PathBuf)]) -> Result<GenesisConfig, failure::Error> {
+    let mut context = Context::default();
+    for (name, path) in args {
+        context.set_arg(name, path.to_str().unwrap().to_owned());
+    }
+    let mut context = GenerateCommonConfig.execute(context)?;
+
+    let node_config = helpers::generate_testnet_config(1, 16_000).remove(0);
+    let services_config = context.get(keys::SERVICES_CONFIG)?;
+    let common_config = serde_json::from_value(json!({
+        "general_config": {},
+        "consensus_config": node_config.genesis.consensus,
+        "services_config": services_config,
+    }))?;
+    context.set(keys::COMMON_CONFIG, common_config);
+    context.set(keys::NODE_CONFIG, node_config);
+
+    let context = Finalize.execute(context)?;
+    config::from_context(&context)
+}
+
+/// generate-template and finalize round trip test
+#[test]
+fn test_config_round_trip() {
+    let (operator, _) = crypto::gen_keypair();
+    let path = write_temp("config.toml", &config_toml(&operator));
+
+    let genesis = round_trip(&[("queue-config", &path)]).unwrap();
+    assert_eq!(
+        genesis.config,
+        ServiceConfig {
+            operators: vec![operator],
+            queues: vec![QueueDefinition {
+                name: "iphone".to_owned(),
+                stores: vec!["store".to_owned()],
+            }],
+            stock: 10,
+            claim_window: 3,
+            deposit: 50,
+            max_pause: 5,
+        }
+    );
+    assert_eq!(genesis.snapshot, None);
+    fs::remove_file(path).unwrap();
+}
+
+/// default configuration round trip test
+#[test]
+fn test_config_round_trip_default() {
+    let genesis = round_trip(&[]).unwrap();
+    assert_eq!(genesis, GenesisConfig::default());
+}
+
+/// snapshot round trip test
+#[test]
+fn test_snapshot_round_trip() {
+    let (operator, operator_key) = crypto::gen_keypair();
+    let snapshot = signed_snapshot(&operator, &operator_key);
+    let config_path = write_temp("snapshot-config.toml", &config_toml(&operator));
+    let snapshot_path = write_temp("snapshot.json", &serde_json::to_string(&snapshot).unwrap());
+
+    let genesis = round_trip(&[
+        ("queue-config", &config_path),
+        ("queue-snapshot", &snapshot_path),
+    ])
+    .unwrap();
+    assert_eq!(genesis.config.operators, vec![operator]);
+    assert_eq!(genesis.snapshot, Some(snapshot));
+
+    // a snapshot signed by another key is rejected by `generate-template`
+    let (pk, sk) = crypto::gen_keypair();
+    let snapshot = signed_snapshot(&pk, &sk);
+    fs::write(&snapshot_path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+    assert!(round_trip(&[
+        ("queue-config", &config_path),
+        ("queue-snapshot", &snapshot_path),
+    ])
+    .is_err());
+
+    fs::remove_file(config_path).unwrap();
+    fs::remove_file(snapshot_path).unwrap();
+}
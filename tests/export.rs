@@ -0,0 +1,122 @@
+//! Tests of the queue state export.
+
+use exonum::crypto::{self, PublicKey};
+#[cfg(feature = "export")]
+use exonum_merkledb::{Database, DbOptions, RocksDB};
+use exonum_testkit::{TestKit, TestKitBuilder};
+
+#[cfg(feature = "export")]
+use std::{env, fs};
+
+#[cfg(feature = "export")]
+use iphone_queue::schema::Schema;
+use iphone_queue::{
+    export::{self, ExportFormat, ExportRecord, ExportStatus},
+    transactions::{Add, Remove},
+    Service,
+};
+
+fn add(testkit: &mut TestKit, key: &PublicKey, timestamp: u64) {
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(Add::sign(&pk, key, timestamp, &sk));
+}
+
+/// JSON Lines export test
+#[test]
+fn test_export_jsonl() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    add(&mut testkit, &pk1, 100);
+    add(&mut testkit, &pk2, 50);
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(Remove::sign(&pk, &pk1, &sk));
+
+    let snapshot = testkit.snapshot();
+    let records = export::export_records(&snapshot);
+    let mut output = Vec::new();
+    export::write_records(&records, ExportFormat::JsonLines, &mut output).unwrap();
+
+    let parsed = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<ExportRecord>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(parsed, records);
+
+    // records are ordered by tickets
+    assert_eq!(parsed[0].key, pk1);
+    assert_eq!(parsed[0].status, ExportStatus::Removed);
+    assert_eq!(parsed[0].history.len(), 2);
+    assert_eq!(parsed[0].history[0].height, Some(1));
+    assert_eq!(parsed[0].history[1].height, Some(3));
+    assert_eq!(parsed[1].key, pk2);
+    assert_eq!(parsed[1].status, ExportStatus::Waiting);
+    assert_eq!(parsed[1].timestamp, 50);
+}
+
+/// CSV export test
+#[test]
+fn test_export_csv() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    let (key, _) = crypto::gen_keypair();
+    add(&mut testkit, &key, 100);
+
+    let snapshot = testkit.snapshot();
+    let records = export::export_records(&snapshot);
+    let mut output = Vec::new();
+    export::write_records(&records, ExportFormat::Csv, &mut output).unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "key,ticket,timestamp,status,history_len,history_hash,history"
+    );
+    assert_eq!(
+        lines[1],
+        format!(
+            "{},1,100,waiting,1,{},{}",
+            key.to_hex(),
+            records[0].history_hash.to_hex(),
+            records[0].history[0].tx_hash.to_hex()
+        )
+    );
+    assert_eq!("csv".parse::<ExportFormat>().unwrap(), ExportFormat::Csv);
+    assert!("xml".parse::<ExportFormat>().is_err());
+}
+
+/// Read-only database export test
+#[cfg(feature = "export")]
+#[test]
+fn test_export_read_only() {
+    let path = env::temp_dir().join(format!(
+        "iphone-queue-export-{}",
+        crypto::gen_keypair().0.to_hex()
+    ));
+    let (pk1, _) = crypto::gen_keypair();
+    let (pk2, _) = crypto::gen_keypair();
+    let db = RocksDB::open(&path, &DbOptions::default()).unwrap();
+    let fork = db.fork();
+    {
+        let mut schema = Schema::new(&fork);
+        schema.add_participant(&pk1, 100, false, false, 0, &crypto::hash(&[1]));
+        schema.add_participant(&pk2, 50, true, false, 0, &crypto::hash(&[2]));
+    }
+    db.merge(fork.into_patch()).unwrap();
+    let expected = export::export_records(&db.snapshot());
+
+    // the database stays open for writes, read-only access doesn't need the lock
+    let snapshot = export::open_read_only(&path).unwrap();
+    assert_eq!(export::export_records(&snapshot), expected);
+    assert_eq!(expected.len(), 2);
+    assert_eq!(expected[1].history[0].tx_hash, crypto::hash(&[2]));
+
+    drop(db);
+    fs::remove_dir_all(&path).unwrap();
+    assert!(export::open_read_only(&path).is_err());
+}
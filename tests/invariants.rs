@@ -0,0 +1,339 @@
+//! Property-based tests for the queue invariants.
+//!
+//! Random sequences of `Add`, `BookSlot`, `Buy`, `Remove`, `Pause` and
+//! `Resume` transactions are executed on the testkit and compared with a
+//! simple model of the queue, including the expiration of pauses.
+
+#[macro_use]
+extern crate proptest;
+
+use exonum::{
+    blockchain,
+    crypto::{self, Hash, PublicKey, SecretKey},
+    messages::{RawTransaction, Signed},
+};
+use exonum_merkledb::ObjectHash;
+use exonum_testkit::{TestKit, TestKitBuilder};
+use proptest::{collection::vec, prelude::*};
+
+use std::collections::HashMap;
+
+use iphone_queue::{
+    config::ServiceConfig,
+    invariants,
+    participant::Participant,
+    schema::Schema,
+    transactions::{Add, BookSlot, Buy, Pause, PublishSlot, Remove, Resume},
+    Service,
+};
+
+const PARTICIPANTS: usize = 4;
+const STORE: &str = "store";
+const MAX_PAUSE: u64 = 3;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Add(usize, u64),
+    Book(usize),
+    Buy(usize),
+    Remove(usize),
+    Pause(usize),
+    Resume(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..PARTICIPANTS, 0..100u64).prop_map(|(i, ts)| Op::Add(i, ts)),
+        (0..PARTICIPANTS).prop_map(Op::Book),
+        (0..PARTICIPANTS).prop_map(Op::Buy),
+        (0..PARTICIPANTS).prop_map(Op::Remove),
+        (0..PARTICIPANTS).prop_map(Op::Pause),
+        (0..PARTICIPANTS).prop_map(Op::Resume),
+    ]
+}
+
+#[derive(Debug, Default)]
+struct ModelParticipant {
+    timestamp: u64,
+    booked: bool,
+    bought: bool,
+    removed: bool,
+    paused: bool,
+    paused_at: u64,
+}
+
+/// Expected queue state built from successfully executed transactions.
+#[derive(Debug, Default)]
+struct Model {
+    participants: HashMap<usize, ModelParticipant>,
+}
+
+impl Model {
+    fn head(&self) -> Option<usize> {
+        self.participants
+            .iter()
+            .filter(|(_, p)| !p.bought && !p.removed && !p.paused)
+            .min_by_key(|(_, p)| p.timestamp)
+            .map(|(&i, _)| i)
+    }
+
+    fn is_waiting(&self, i: usize) -> bool {
+        self.participants
+            .get(&i)
+            .map_or(false, |p| !p.bought && !p.removed)
+    }
+
+    fn is_paused(&self, i: usize) -> bool {
+        self.participants.get(&i).map_or(false, |p| p.paused)
+    }
+
+    /// Returns whether the operation executed at `height` should succeed
+    /// and applies it if so.
+    fn apply(&mut self, op: &Op, height: u64) -> bool {
+        match *op {
+            Op::Add(i, ts) => {
+                if self.participants.contains_key(&i) {
+                    return false;
+                }
+                // Make timestamps unique so the head is unambiguous.
+                let timestamp = ts * PARTICIPANTS as u64 + i as u64;
+                self.participants.insert(
+                    i,
+                    ModelParticipant {
+                        timestamp,
+                        ..ModelParticipant::default()
+                    },
+                );
+            }
+            Op::Book(i) => {
+                if !self.is_waiting(i) || self.participants[&i].booked || self.head() != Some(i) {
+                    return false;
+                }
+                self.participants.get_mut(&i).unwrap().booked = true;
+            }
+            Op::Buy(i) => {
+                if !self.is_waiting(i) || !self.participants[&i].booked || self.head() != Some(i) {
+                    return false;
+                }
+                self.participants.get_mut(&i).unwrap().bought = true;
+            }
+            Op::Remove(i) => match self.participants.get_mut(&i) {
+                Some(ref mut p) if !p.bought && !p.removed => {
+                    p.removed = true;
+                    p.paused = false;
+                }
+                _ => return false,
+            },
+            Op::Pause(i) => {
+                if !self.is_waiting(i) || self.is_paused(i) {
+                    return false;
+                }
+                let p = self.participants.get_mut(&i).unwrap();
+                p.paused = true;
+                p.paused_at = height;
+            }
+            Op::Resume(i) => {
+                if !self.is_waiting(i) || !self.is_paused(i) {
+                    return false;
+                }
+                self.participants.get_mut(&i).unwrap().paused = false;
+            }
+        }
+        true
+    }
+
+    /// Removes participants whose pause is over at the end of the block at
+    /// `height`.
+    fn expire(&mut self, height: u64) {
+        for p in self.participants.values_mut() {
+            if p.paused && height >= p.paused_at + MAX_PAUSE {
+                p.paused = false;
+                p.removed = true;
+            }
+        }
+    }
+}
+
+fn execute(testkit: &mut TestKit, tx: Signed<RawTransaction>) -> bool {
+    let hash = tx.hash();
+    testkit.create_block_with_transaction(tx);
+    let snapshot = testkit.snapshot();
+    blockchain::Schema::new(&snapshot)
+        .transaction_results()
+        .get(&hash)
+        .unwrap()
+        .0
+        .is_ok()
+}
+
+fn transaction(
+    op: &Op,
+    keys: &[(PublicKey, SecretKey)],
+    slot: &Hash,
+) -> Signed<RawTransaction> {
+    let (pk, sk) = crypto::gen_keypair();
+    match *op {
+        Op::Add(i, ts) => {
+            let timestamp = ts * PARTICIPANTS as u64 + i as u64;
+            Add::sign(&pk, &keys[i].0, timestamp, &sk)
+        }
+        Op::Book(i) => BookSlot::sign(&pk, &keys[i].0, slot, &sk),
+        Op::Buy(i) => Buy::sign(&pk, &keys[i].0, STORE, &sk),
+        Op::Remove(i) => Remove::sign(&pk, &keys[i].0, &sk),
+        Op::Pause(i) => Pause::sign(&keys[i].0, &keys[i].0, &keys[i].1),
+        Op::Resume(i) => Resume::sign(&keys[i].0, &keys[i].0, &keys[i].1),
+    }
+}
+
+fn participants(testkit: &TestKit) -> HashMap<PublicKey, Participant> {
+    let snapshot = testkit.snapshot();
+    Schema::new(&snapshot).participants().iter().collect()
+}
+
+/// Checks if the last history entry of the participant is an expiration.
+fn expired(testkit: &TestKit, key: &PublicKey) -> bool {
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    schema
+        .participant_history(key)
+        .last()
+        .map_or(false, |hash| schema.history_events().contains(&hash))
+}
+
+/// Checks the pause and resume preconditions against the stored participant.
+fn precondition(op: &Op, keys: &[(PublicKey, SecretKey)], testkit: &TestKit) -> Option<bool> {
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let allowed = match *op {
+        Op::Pause(i) => schema
+            .participant(&keys[i].0)
+            .map_or(false, |p| invariants::check_pause(&p).is_ok()),
+        Op::Resume(i) => schema
+            .participant(&keys[i].0)
+            .map_or(false, |p| invariants::check_resume(&p).is_ok()),
+        _ => return None,
+    };
+    Some(allowed)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn queue_invariants_hold(ops in vec(op(), 1..24)) {
+        let (pk, sk) = crypto::gen_keypair();
+        let config = ServiceConfig {
+            operators: vec![pk],
+            max_pause: MAX_PAUSE,
+            ..ServiceConfig::default()
+        };
+        let mut testkit = TestKitBuilder::validator().with_service(Service::new(config)).create();
+        let keys = (0..PARTICIPANTS)
+            .map(|_| crypto::gen_keypair())
+            .collect::<Vec<_>>();
+
+        let slot = PublishSlot::sign(&pk, STORE, 1000, 2000, PARTICIPANTS as u64, &sk);
+        let slot_hash = slot.hash();
+        prop_assert!(execute(&mut testkit, slot));
+
+        let mut model = Model::default();
+        let mut purchases = vec![0; PARTICIPANTS];
+
+        for op in &ops {
+            // Purchases must be made strictly in queue order.
+            let expected_head = model.head();
+            let height = testkit.height().next().0;
+            let expected = model.apply(op, height);
+            model.expire(height);
+            if let Some(allowed) = precondition(op, &keys, &testkit) {
+                prop_assert_eq!(expected, allowed, "{:?}", op);
+            }
+
+            let before = participants(&testkit);
+            let actual = execute(&mut testkit, transaction(op, &keys, &slot_hash));
+            prop_assert_eq!(expected, actual, "{:?}", op);
+
+            // Every change is either a transaction or an expired pause.
+            let after = participants(&testkit);
+            for (key, updated) in &after {
+                match before.get(key) {
+                    Some(stored) if stored.object_hash() == updated.object_hash() => {}
+                    Some(stored) if expired(&testkit, key) => {
+                        prop_assert!(invariants::check_expiration(stored, updated).is_ok());
+                    }
+                    Some(stored) => {
+                        prop_assert!(invariants::check_transition(stored, updated).is_ok());
+                    }
+                    None => prop_assert_eq!(updated.history_len, 1),
+                }
+            }
+            for (i, (key, _)) in keys.iter().enumerate() {
+                let stored = after.get(key);
+                let modeled = model.participants.get(&i);
+                prop_assert_eq!(stored.map(|p| p.paused), modeled.map(|p| p.paused));
+                prop_assert_eq!(stored.map(|p| p.removed), modeled.map(|p| p.removed));
+            }
+
+            if let Op::Buy(i) = *op {
+                if actual {
+                    prop_assert_eq!(expected_head, Some(i));
+                    purchases[i] += 1;
+                }
+            }
+        }
+
+        // At most one purchase per key.
+        prop_assert!(purchases.iter().all(|&count| count <= 1));
+
+        // History length and hash match the stored participant.
+        let snapshot = testkit.snapshot();
+        let schema = Schema::new(&snapshot);
+        for (key, participant) in schema.participants().iter() {
+            let history = schema.participant_history(&key);
+            prop_assert_eq!(history.len(), participant.history_len);
+            prop_assert_eq!(history.object_hash(), participant.history_hash);
+            prop_assert!(!(participant.have_bought && participant.removed));
+            prop_assert!(!(participant.paused && (participant.have_bought || participant.removed)));
+        }
+    }
+}
+
+/// pause state transitions test
+#[test]
+fn test_pause_transitions() {
+    let (key, _) = crypto::gen_keypair();
+    let waiting = Participant::new(&key, 100, false, false, 1, &Hash::zero(), 1);
+    let paused = waiting.clone().pause(5, &crypto::hash(b"pause"));
+    assert!(invariants::check_pause(&waiting).is_ok());
+    assert!(invariants::check_pause(&paused).is_err());
+    assert!(invariants::check_resume(&waiting).is_err());
+    assert!(invariants::check_resume(&paused).is_ok());
+    assert!(invariants::check_transition(&waiting, &paused).is_ok());
+
+    // the place of the participant never changes
+    let moved = Participant {
+        ticket: 2,
+        ..paused.clone()
+    };
+    assert!(invariants::check_transition(&waiting, &moved).is_err());
+    let removed_paused = Participant {
+        removed: true,
+        ..paused.clone()
+    };
+    assert!(invariants::check_transition(&waiting, &removed_paused).is_err());
+
+    // only waiting participants expire with the event appended to the history
+    let expire = crypto::hash(b"expire");
+    assert!(invariants::check_expiration(&paused, &paused.clone().expire(&expire)).is_ok());
+    assert!(invariants::check_expiration(&waiting, &waiting.clone().expire(&expire)).is_ok());
+    let bought = waiting.clone().buy(&crypto::hash(b"buy"));
+    assert!(invariants::check_expiration(&bought, &bought.clone().expire(&expire)).is_err());
+    let unrecorded = Participant {
+        history_len: paused.history_len,
+        ..paused.clone().expire(&expire)
+    };
+    assert!(invariants::check_expiration(&paused, &unrecorded).is_err());
+    let removed = paused.clone().remove(&crypto::hash(b"remove"));
+    assert!(invariants::check_pause(&removed).is_err());
+    assert!(invariants::check_resume(&removed).is_err());
+}
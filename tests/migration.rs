@@ -0,0 +1,156 @@
+//! Tests of the stored data migrations.
+
+use exonum::crypto::{self, Hash, PublicKey};
+use exonum_merkledb::{BinaryValue, Database, Fork, ObjectHash, ProofMapIndex, TemporaryDB};
+use exonum_testkit::{txvec, TestKitBuilder};
+
+use iphone_queue::{
+    migration::{self, CURRENT_VERSION},
+    participant::Participant,
+    schema::{Schema, PARTICIPANT_TYPES_TABLE},
+    stats::QueueStats,
+    transactions::{Add, Remove},
+    Service,
+};
+
+/// Participants encoded with the baseline layout, one per line.
+const PARTICIPANTS_V0: &str = include_str!("fixtures/participants_v0.hex");
+
+fn legacy_key(byte: u8) -> PublicKey {
+    PublicKey::from_slice(&[byte; 32]).unwrap()
+}
+
+/// Writes the fixture records into the fork as they were stored by the
+/// layout of the fixture.
+fn write_fixtures(fork: &Fork, fixtures: &str) {
+    let mut participants: ProofMapIndex<_, PublicKey, Vec<u8>> =
+        ProofMapIndex::new(PARTICIPANT_TYPES_TABLE, fork);
+    for line in fixtures.lines() {
+        let bytes = hex::decode(line).unwrap();
+        let participant = Participant::from_bytes(bytes.clone().into()).unwrap();
+        participants.put(&participant.key, bytes);
+    }
+}
+
+/// Writes the fixture records into the database.
+fn load_fixtures(db: &TemporaryDB, fixtures: &str) {
+    let fork = db.fork();
+    write_fixtures(&fork, fixtures);
+    db.merge(fork.into_patch()).unwrap();
+}
+
+/// legacy participants migration test
+#[test]
+fn test_participants_v0_migration() {
+    let db = TemporaryDB::new();
+    load_fixtures(&db, PARTICIPANTS_V0);
+
+    let fork = db.fork();
+    let mut schema = Schema::new(&fork);
+    assert_eq!(schema.schema_version(), 0);
+    let legacy = schema.participant(&legacy_key(2)).unwrap();
+    assert_eq!(legacy.ticket, 0);
+    assert_eq!(legacy.timestamp, 100);
+    assert!(legacy.have_bought);
+
+    assert_eq!(migration::migrate(&mut schema), Some(0));
+    assert_eq!(schema.schema_version(), CURRENT_VERSION);
+
+    // tickets follow the queue order, equal timestamps are ordered by keys
+    let expected = [(2, 1), (4, 2), (1, 3), (3, 4)];
+    for &(byte, ticket) in &expected {
+        let key = legacy_key(byte);
+        let participant = schema.participant(&key).unwrap();
+        assert_eq!(participant.ticket, ticket);
+        assert_eq!(schema.ticket_key(ticket), Some(key));
+    }
+    assert_eq!(schema.last_ticket(), 4);
+
+    // other fields are preserved
+    let removed = schema.participant(&legacy_key(4)).unwrap();
+    assert_eq!(removed.timestamp, 200);
+    assert!(removed.removed && !removed.have_bought);
+    assert_eq!(removed.history_len, 2);
+    assert_eq!(removed.history_hash, Hash::new([0x44; 32]));
+
+    // baseline records are decoded as not paused without a deposit
+    for byte in 1..=4 {
+        let participant = schema.participant(&legacy_key(byte)).unwrap();
+        assert!(!participant.paused);
+        assert_eq!(participant.paused_at, 0);
+        assert_eq!(participant.deposit, 0);
+    }
+
+    // derived indices are filled from the baseline participants
+    assert_eq!(schema.served().iter().collect::<Vec<_>>(), vec![legacy_key(2)]);
+    assert_eq!(
+        schema.queue_stats(),
+        QueueStats {
+            total_added: 4,
+            waiting: 2,
+            bought: 1,
+            removed: 1,
+            ..QueueStats::default()
+        }
+    );
+    let versions = schema.participant_versions(&legacy_key(1));
+    assert_eq!(versions.len(), 1);
+    assert_eq!(
+        schema.versions().get(&legacy_key(1)),
+        Some(versions.object_hash())
+    );
+
+    // migrated data is up to date
+    assert_eq!(migration::migrate(&mut schema), None);
+    assert_eq!(schema.participant(&legacy_key(1)).unwrap().ticket, 3);
+}
+
+/// transactions of the upgrade block migration test
+#[test]
+fn test_upgrade_block_migration() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    let fork = testkit.blockchain_mut().fork();
+    write_fixtures(&fork, PARTICIPANTS_V0);
+    Schema::new(&fork).set_schema_version(0);
+    testkit.blockchain_mut().merge(fork.into_patch()).unwrap();
+
+    // the failed transaction rolls back its migration, the next one migrates
+    let (pk, sk) = crypto::gen_keypair();
+    let (unknown, _) = crypto::gen_keypair();
+    let (added, _) = crypto::gen_keypair();
+    testkit.create_block_with_transactions(txvec![
+        Remove::sign(&pk, &unknown, &sk),
+        Add::sign(&pk, &added, 50, &sk),
+    ]);
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.schema_version(), CURRENT_VERSION);
+
+    // legacy participants keep the first tickets in their queue order
+    let expected = [(2, 1), (4, 2), (1, 3), (3, 4)];
+    for &(byte, ticket) in &expected {
+        assert_eq!(schema.participant(&legacy_key(byte)).unwrap().ticket, ticket);
+    }
+    assert_eq!(schema.participant(&added).unwrap().ticket, 5);
+    assert_eq!(schema.ticket_key(5), Some(added));
+    assert_eq!(schema.last_ticket(), 5);
+    assert_eq!(schema.queue_stats().total_added, 5);
+    assert_eq!(schema.queue_stats().waiting, 3);
+}
+
+/// new chain schema version test
+#[test]
+fn test_new_chain_version() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::default())
+        .create();
+    testkit.create_block();
+
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.schema_version(), CURRENT_VERSION);
+    assert_eq!(schema.last_ticket(), 0);
+}
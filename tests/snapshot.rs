@@ -0,0 +1,279 @@
+//! Tests of the genesis snapshot import.
+
+use exonum::{
+    crypto::{self, PublicKey},
+    helpers::Height,
+};
+use exonum_merkledb::ObjectHash;
+use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
+
+use iphone_queue::{
+    api::{ParticipantInfo, ParticipantQuery, SnapshotProof, SnapshotQuery},
+    config::ServiceConfig,
+    export::{self, ExportStatus},
+    schema::{Schema, ServiceTable},
+    snapshot::{QueueSnapshot, SnapshotParticipant},
+    transactions::{Add, Remove},
+    Service,
+};
+
+fn participant(
+    ticket: u64,
+    timestamp: u64,
+    have_bought: bool,
+    removed: bool,
+) -> SnapshotParticipant {
+    let (key, _) = crypto::gen_keypair();
+    SnapshotParticipant {
+        key,
+        ticket,
+        timestamp,
+        have_bought,
+        removed,
+        paused: false,
+        paused_at: 0,
+    }
+}
+
+fn signed_snapshot() -> QueueSnapshot {
+    let (pk, sk) = crypto::gen_keypair();
+    QueueSnapshot::sign(
+        vec![
+            participant(5, 10, false, false),
+            participant(1, 20, false, false),
+            participant(2, 5, true, false),
+            participant(3, 30, false, true),
+        ],
+        &pk,
+        &sk,
+    )
+}
+
+/// Creates a testkit trusting the signer of the snapshot.
+fn create_testkit(snapshot: &QueueSnapshot) -> TestKit {
+    let config = ServiceConfig {
+        operators: vec![snapshot.signer],
+        ..ServiceConfig::default()
+    };
+    TestKitBuilder::validator()
+        .with_service(Service::new(config).with_snapshot(snapshot.clone()))
+        .create()
+}
+
+fn key_by_ticket(snapshot: &QueueSnapshot, ticket: u64) -> PublicKey {
+    snapshot
+        .participants
+        .iter()
+        .find(|participant| participant.ticket == ticket)
+        .unwrap()
+        .key
+}
+
+/// snapshot import test
+#[test]
+fn test_snapshot_import() {
+    let snapshot = signed_snapshot();
+    snapshot.verify().unwrap();
+    let mut testkit = create_testkit(&snapshot);
+
+    {
+        let view = testkit.snapshot();
+        let schema = Schema::new(&view);
+        assert_eq!(schema.snapshot(), Some(snapshot.info()));
+        assert_eq!(schema.participants().keys().count(), 4);
+        assert_eq!(schema.last_ticket(), 5);
+
+        let stats = schema.queue_stats();
+        assert_eq!(stats.total_added, 4);
+        assert_eq!(stats.waiting, 2);
+        assert_eq!(stats.bought, 1);
+        assert_eq!(stats.removed, 1);
+
+        // the order is restored from timestamps of waiting participants
+        let first = schema.first_participant().unwrap();
+        assert_eq!(first.key, key_by_ticket(&snapshot, 5));
+        assert_eq!(first.history_len, 0);
+        assert_eq!(schema.served().len(), 1);
+
+        // the snapshot is exported back unchanged
+        let exported = export::export_records(&view)
+            .iter()
+            .map(SnapshotParticipant::from)
+            .collect::<Vec<_>>();
+        assert_eq!(exported, snapshot.participants);
+    }
+
+    // new participants get tickets after the snapshot
+    let (key, _) = crypto::gen_keypair();
+    let (pk, sk) = crypto::gen_keypair();
+    testkit.create_block_with_transaction(Add::sign(&pk, &key, 100, &sk));
+    // imported participants continue with their histories
+    let first = key_by_ticket(&snapshot, 5);
+    testkit.create_block_with_transaction(Remove::sign(&pk, &first, &sk));
+
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    assert_eq!(schema.participant(&key).unwrap().ticket, 6);
+    let removed = schema.participant(&first).unwrap();
+    assert!(removed.removed);
+    assert_eq!(removed.history_len, 1);
+    assert_eq!(
+        schema.first_participant().unwrap().key,
+        key_by_ticket(&snapshot, 1)
+    );
+}
+
+/// snapshot api test
+#[test]
+fn test_snapshot_api() {
+    let snapshot = signed_snapshot();
+    let testkit = create_testkit(&snapshot);
+    let api = testkit.api();
+
+    let proof: SnapshotProof = api
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&SnapshotQuery {})
+        .get("v1/iphone_queue/snapshot")
+        .unwrap();
+    let info = proof.snapshot.unwrap();
+    assert_eq!(info.hash, snapshot.hash());
+    assert_eq!(info.participants, 4);
+
+    let view = testkit.snapshot();
+    let table_hash = Schema::new(&view).table_hash(ServiceTable::Snapshot);
+    assert_eq!(table_hash, info.object_hash());
+
+    // imported participants are returned without history
+    let key = key_by_ticket(&snapshot, 1);
+    let info: ParticipantInfo = api
+        .public(ApiKind::Service("iphone_queue"))
+        .query(&ParticipantQuery::by_key(key))
+        .get("v1/iphone_queue/info")
+        .unwrap();
+    assert!(info.participant_history.is_none());
+}
+
+/// snapshot signer test
+#[test]
+fn test_snapshot_signer() {
+    let snapshot = signed_snapshot();
+    let (operator, _) = crypto::gen_keypair();
+
+    // an empty list of operators trusts no signer
+    assert!(ServiceConfig::default().verify_snapshot(&snapshot).is_err());
+    let config = ServiceConfig {
+        operators: vec![operator],
+        ..ServiceConfig::default()
+    };
+    assert!(config.verify_snapshot(&snapshot).is_err());
+    let config = ServiceConfig {
+        operators: vec![operator, snapshot.signer],
+        ..ServiceConfig::default()
+    };
+    config.verify_snapshot(&snapshot).unwrap();
+}
+
+/// snapshot from an untrusted signer is not imported test
+#[test]
+#[should_panic(expected = "Invalid queue snapshot")]
+fn test_snapshot_untrusted_signer() {
+    let snapshot = signed_snapshot();
+    TestKitBuilder::validator()
+        .with_service(Service::default().with_snapshot(snapshot))
+        .create();
+}
+
+/// snapshot verification test
+#[test]
+fn test_snapshot_verify() {
+    let mut tampered = signed_snapshot();
+    tampered.participants[0].timestamp = 0;
+    assert!(tampered.verify().is_err());
+
+    let (pk, sk) = crypto::gen_keypair();
+    let imported = participant(1, 10, false, false);
+    let duplicate_key = SnapshotParticipant {
+        ticket: 2,
+        ..imported.clone()
+    };
+    let snapshot = QueueSnapshot::sign(vec![imported.clone(), duplicate_key], &pk, &sk);
+    assert!(snapshot.verify().is_err());
+
+    let duplicate_ticket = participant(1, 20, false, false);
+    let snapshot = QueueSnapshot::sign(vec![imported, duplicate_ticket], &pk, &sk);
+    assert!(snapshot.verify().is_err());
+
+    let paused_bought = SnapshotParticipant {
+        paused: true,
+        paused_at: 10,
+        ..participant(1, 10, true, false)
+    };
+    let snapshot = QueueSnapshot::sign(vec![paused_bought], &pk, &sk);
+    assert!(snapshot.verify().is_err());
+
+    let pause_height_only = SnapshotParticipant {
+        paused_at: 10,
+        ..participant(1, 10, false, false)
+    };
+    let snapshot = QueueSnapshot::sign(vec![pause_height_only], &pk, &sk);
+    assert!(snapshot.verify().is_err());
+}
+
+/// paused participants snapshot import test
+#[test]
+fn test_snapshot_import_paused() {
+    let (pk, sk) = crypto::gen_keypair();
+    let paused = SnapshotParticipant {
+        paused: true,
+        paused_at: 500,
+        ..participant(1, 10, false, false)
+    };
+    let paused_early = SnapshotParticipant {
+        paused: true,
+        paused_at: 1,
+        ..participant(3, 30, false, false)
+    };
+    let snapshot = QueueSnapshot::sign(
+        vec![
+            paused.clone(),
+            participant(2, 20, false, false),
+            paused_early.clone(),
+        ],
+        &pk,
+        &sk,
+    );
+    snapshot.verify().unwrap();
+    let config = ServiceConfig {
+        operators: vec![snapshot.signer],
+        max_pause: 2,
+        ..ServiceConfig::default()
+    };
+    let mut testkit = TestKitBuilder::validator()
+        .with_service(Service::new(config).with_snapshot(snapshot.clone()))
+        .create();
+
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    let imported = schema.participant(&paused.key).unwrap();
+    assert!(imported.paused);
+    // the pause keeps its height on the old network
+    assert_eq!(imported.paused_at, 500);
+    assert_eq!(schema.pause_deadlines(502).len(), 1);
+    assert_eq!(
+        schema.first_participant().unwrap().key,
+        snapshot.participants[1].key
+    );
+
+    let records = export::export_records(&view);
+    assert_eq!(records[0].status, ExportStatus::Paused);
+    let exported = SnapshotParticipant::from(&records[0]);
+    assert!(exported.paused);
+    assert_eq!(exported.paused_at, 500);
+
+    // the pause started at height 1 expires at height 3
+    testkit.create_blocks_until(Height(3));
+    let view = testkit.snapshot();
+    let schema = Schema::new(&view);
+    assert!(schema.participant(&paused_early.key).unwrap().removed);
+    assert!(schema.participant(&paused.key).unwrap().paused);
+}
@@ -0,0 +1,81 @@
+//! Tests of the testkit helpers, run with `cargo test --features testing`.
+#![cfg(feature = "testing")]
+
+use exonum::crypto::{self, Hash};
+
+use iphone_queue::{
+    config::ServiceConfig,
+    testing::{QueueTestKit, SLOT_END, SLOT_START},
+    transactions::{Error, PublishSlot},
+};
+
+/// scenario builder test
+#[test]
+fn test_queue_scenario() {
+    let mut kit = QueueTestKit::new();
+    let keys = kit.queue_with_random_timestamps(5, 1000);
+    let slot = kit.slot("store", 5);
+
+    // only the head of the queue may book a slot
+    let tx = kit.book_slot(&keys[1], &slot);
+    kit.create_block();
+    kit.assert_tx_error(&tx, Error::ParticipantIsNotFirst);
+
+    kit.serve(&keys[0], &slot, "store");
+    let verified = kit.verified_participant(&keys[0]);
+    assert!(verified.participant.unwrap().have_bought);
+    assert_eq!(verified.history.len(), 3);
+
+    let (unknown, _) = crypto::gen_keypair();
+    assert!(kit.verified_participant(&unknown).participant.is_none());
+}
+
+/// operator key test
+#[test]
+fn test_operator_key() {
+    let (other_operator, _) = crypto::gen_keypair();
+    let mut kit = QueueTestKit::with_config(ServiceConfig {
+        operators: vec![other_operator],
+        ..ServiceConfig::default()
+    });
+    assert_ne!(kit.operator_key(), other_operator);
+
+    // the testkit operator is added to the configured operators
+    let keys = kit.queue_with_timestamps(&[10, 20]);
+    let slot = kit.slot("store", 1);
+    kit.serve(&keys[0], &slot, "store");
+
+    // other keys may not publish slots
+    let (pk, sk) = crypto::gen_keypair();
+    let tx = kit.send(&PublishSlot::sign(&pk, "store", SLOT_START, SLOT_END, 1, &sk));
+    kit.create_block();
+    kit.assert_tx_error(&tx, Error::NotOperator);
+}
+
+/// random queue order test
+#[test]
+fn test_random_queue_order() {
+    let mut kit = QueueTestKit::new();
+    let keys = kit.queue_with_random_timestamps(10, 1000);
+
+    let timestamps = keys
+        .iter()
+        .map(|key| kit.verified_participant(key).participant.unwrap().timestamp)
+        .collect::<Vec<_>>();
+    assert!(timestamps.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // the head of the queue is the earliest participant, not the first added
+    let slot = kit.slot("store", 1);
+    kit.serve(&keys[0], &slot, "store");
+}
+
+/// empty attestation identity test
+#[test]
+fn test_empty_identity() {
+    let mut kit = QueueTestKit::new();
+    let keys = kit.queue_with_timestamps(&[10]);
+
+    let tx = kit.attest(&keys[0], &Hash::zero(), "");
+    kit.create_block();
+    kit.assert_tx_error(&tx, Error::EmptyIdentity);
+}